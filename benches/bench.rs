@@ -1,15 +1,15 @@
 #[macro_use]
 extern crate criterion;
 
-use criterion::{Criterion, Throughput, BenchmarkId};
+use criterion::{Criterion, Throughput, BenchmarkId, BatchSize};
 
-#[derive(Default)]
+#[derive(Default, Clone, Copy)]
 struct Small(usize);
 
-#[derive(Default)]
+#[derive(Default, Clone, Copy)]
 struct Medium([usize; 4]);
 
-#[derive(Default)]
+#[derive(Default, Clone, Copy)]
 struct Big([usize; 32]);
 
 macro_rules! mk_alloc {
@@ -27,6 +27,15 @@ macro_rules! mk_alloc {
 mk_alloc!(simple, arena::SimpleArena::new());
 mk_alloc!(arena, arena::Arena::new());
 mk_alloc!(rust_typed_arena, typed_arena::Arena::new());
+mk_alloc!(inline, arena::InlineArena::<_, 16>::new());
+
+fn dropless<T: Default + Copy>(n: usize) {
+  let arena = arena::DroplessArena::new();
+  for _ in 0..n {
+    let val = arena.alloc(T::default());
+    criterion::black_box(val);
+  }
+}
 
 fn criterion_benchmark(c: &mut Criterion) {
   macro_rules! mk_bench {
@@ -36,6 +45,7 @@ fn criterion_benchmark(c: &mut Criterion) {
         group.throughput(Throughput::Elements(n as u64));
         group.bench_with_input(BenchmarkId::new("simple", n), &n, |b, &i| b.iter(|| simple::<$elem>(i)));
         group.bench_with_input(BenchmarkId::new("arena", n), &n, |b, &i| b.iter(|| arena::<$elem>(i)));
+        group.bench_with_input(BenchmarkId::new("dropless", n), &n, |b, &i| b.iter(|| dropless::<$elem>(i)));
         group.bench_with_input(BenchmarkId::new("rust-typed-arena", n), &n, |b, &i| b.iter(|| rust_typed_arena::<$elem>(i)));
       }
       group.finish();
@@ -44,6 +54,125 @@ fn criterion_benchmark(c: &mut Criterion) {
   mk_bench!("small", Small);
   mk_bench!("medium", Medium);
   mk_bench!("big", Big);
+
+  let mut group = c.benchmark_group("in_place");
+  for n in (1..6).map(|n| n * 2000) {
+    group.throughput(Throughput::Elements(n as u64));
+    group.bench_with_input(BenchmarkId::new("alloc", n), &n, |b, &n| b.iter(|| arena::<Big>(n)));
+    group.bench_with_input(BenchmarkId::new("alloc_with", n), &n, |b, &n| b.iter(|| {
+      let arena = arena::Arena::new();
+      for _ in 0..n {
+        criterion::black_box(arena.alloc_with(Big::default));
+      }
+    }));
+    group.bench_with_input(BenchmarkId::new("simple_alloc", n), &n, |b, &n| b.iter(|| simple::<Big>(n)));
+    group.bench_with_input(BenchmarkId::new("simple_alloc_default", n), &n, |b, &n| b.iter(|| {
+      let arena = arena::SimpleArena::<Big>::new();
+      for _ in 0..n {
+        criterion::black_box(arena.alloc_default());
+      }
+    }));
+  }
+  group.finish();
+
+  // at most 16 elements are allocated inline, without calling the allocator
+  let mut group = c.benchmark_group("inline");
+  for n in [4, 16, 64] {
+    group.throughput(Throughput::Elements(n as u64));
+    group.bench_with_input(BenchmarkId::new("inline", n), &n, |b, &i| b.iter(|| inline::<Small>(i)));
+    group.bench_with_input(BenchmarkId::new("simple", n), &n, |b, &i| b.iter(|| simple::<Small>(i)));
+    group.bench_with_input(BenchmarkId::new("arena", n), &n, |b, &i| b.iter(|| arena::<Small>(i)));
+  }
+  group.finish();
+
+  let mut group = c.benchmark_group("chunk_capacity");
+  group.throughput(Throughput::Elements(1000));
+  group.bench_function("new", |b| b.iter(|| simple::<Small>(1000)));
+  group.bench_function("with_chunk_capacity", |b| b.iter(|| {
+    let arena = arena::SimpleArena::<Small>::with_chunk_capacity(256);
+    for _ in 0..1000 {
+      criterion::black_box(arena.alloc(Small::default()));
+    }
+  }));
+  group.finish();
+
+  let mut group = c.benchmark_group("array");
+  for n in (1..6).map(|n| n * 2000) {
+    group.throughput(Throughput::Elements(n as u64));
+    group.bench_with_input(BenchmarkId::new("alloc_array", n), &n, |b, &n| b.iter(|| {
+      let arena = arena::Arena::new();
+      for _ in 0..n {
+        criterion::black_box(arena.alloc_array([Small::default(), Small::default()]));
+      }
+    }));
+    group.bench_with_input(BenchmarkId::new("alloc_slice", n), &n, |b, &n| b.iter(|| {
+      let arena = arena::Arena::new();
+      for _ in 0..n {
+        criterion::black_box(arena.alloc_slice(vec![Small::default(), Small::default()]));
+      }
+    }));
+  }
+  group.finish();
+
+  let mut group = c.benchmark_group("slice_copy");
+  for n in [1000, 100000] {
+    let src = vec![0usize; n];
+    group.throughput(Throughput::Elements(n as u64));
+    group.bench_with_input(BenchmarkId::new("alloc_slice_copy", n), &src, |b, src| b.iter(|| {
+      let arena = arena::Arena::new();
+      criterion::black_box(arena.alloc_slice_copy(src));
+    }));
+    group.bench_with_input(BenchmarkId::new("alloc_slice", n), &src, |b, src| b.iter(|| {
+      let arena = arena::Arena::new();
+      criterion::black_box(arena.alloc_slice(src.to_vec()));
+    }));
+  }
+  group.finish();
+
+  let mut group = c.benchmark_group("slice_default");
+  for n in [1000, 100000] {
+    group.throughput(Throughput::Elements(n as u64));
+    group.bench_with_input(BenchmarkId::new("alloc_slice_default", n), &n, |b, &n| b.iter(|| {
+      let arena = arena::Arena::<Medium>::new();
+      criterion::black_box(arena.alloc_slice_default(n));
+    }));
+    group.bench_with_input(BenchmarkId::new("alloc_slice", n), &n, |b, &n| b.iter(|| {
+      let arena = arena::Arena::new();
+      criterion::black_box(arena.alloc_slice(vec![Medium::default(); n]));
+    }));
+  }
+  group.finish();
+
+  let mut group = c.benchmark_group("many");
+  for n in [1000, 100000] {
+    group.throughput(Throughput::Elements(n as u64));
+    group.bench_with_input(BenchmarkId::new("alloc_many", n), &n, |b, &n| b.iter(|| {
+      let arena = arena::Arena::new();
+      criterion::black_box(arena.alloc_many(n, Medium::default()));
+    }));
+    group.bench_with_input(BenchmarkId::new("alloc_slice", n), &n, |b, &n| b.iter(|| {
+      let arena = arena::Arena::new();
+      criterion::black_box(arena.alloc_slice(vec![Medium::default(); n]));
+    }));
+  }
+  group.finish();
+
+  let mut group = c.benchmark_group("slice_large");
+  for n in [1 << 16, 1 << 20] {
+    let src = vec![0f64; n];
+    group.throughput(Throughput::Elements(n as u64));
+    group.bench_with_input(BenchmarkId::new("alloc_slice_copy", n), &src, |b, src| b.iter_with_large_drop(|| {
+      let arena = arena::Arena::new();
+      criterion::black_box(arena.alloc_slice_copy(src));
+      arena
+    }));
+    group.bench_with_input(BenchmarkId::new("alloc_slice", n), &src, |b, src| b.iter_batched(|| src.clone(), |v| {
+      let arena = arena::Arena::new();
+      criterion::black_box(arena.alloc_slice(v));
+      arena
+    }, BatchSize::LargeInput));
+  }
+  group.finish();
 }
 
 criterion_group!(bench, criterion_benchmark);
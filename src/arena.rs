@@ -1,10 +1,22 @@
 #[cfg(feature = "no_std")]
-use alloc::vec::Vec;
+use alloc::{vec::{self, Vec}, boxed::Box};
 #[cfg(not(feature = "no_std"))]
-use std::vec::Vec;
-use core::mem;
+use std::vec::{self, Vec};
+use core::mem::{self, MaybeUninit};
+use core::ptr::{self, NonNull};
+use core::iter;
 use core::slice;
+use core::alloc::Layout;
 use core::cell::UnsafeCell;
+use core::ops::{Index, IndexMut};
+#[cfg(all(feature = "bytemuck", feature = "no_std"))]
+use alloc::alloc::alloc_zeroed;
+#[cfg(all(feature = "bytemuck", not(feature = "no_std")))]
+use std::alloc::alloc_zeroed;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::marker::PhantomData;
+use crate::{AllocError, ArenaBox, BrandedArena, rc::{ArenaRc, RcSlot}, reserve::{Chunks, Tails, Reserve, ReserveSlot}};
 
 /// An arena of objects of type `T`.
 ///
@@ -24,15 +36,75 @@ use core::cell::UnsafeCell;
 pub struct Arena<T>(UnsafeCell<Inner<T>>);
 
 struct Inner<T> {
+  // declared first, so that committed slices are dropped before the chunks holding them are freed
+  committed: Committed<T>,
   cur: Vec<T>,
   rest: Vec<Vec<T>>,
+  // `ends[i]` is the number of elements in `rest[..=i]`, used to locate an element by index
+  ends: Vec<usize>,
+  // slots `end..cur.capacity()` are reserved by `alloc_uninit`, and are never dropped by the arena
+  end: usize,
+  // the live `Reserve`s and `ReserveSlot`s, the innermost of which sets `end` to `cur.len()` while writing past it
+  tails: Tails<T>,
+  // identifies the arena in checkpoints
+  id: usize,
 }
 
 impl<T> Arena<T> {
   /// Construct a new arena.
   #[inline]
-  pub fn new() -> Self {
-    Self(UnsafeCell::new(Inner { cur: Vec::with_capacity(1), rest: Vec::new() }))
+  pub fn new() -> Self { Self::with_capacity(1) }
+
+  /// Construct a new arena whose first chunk can hold `n` elements.
+  ///
+  /// The first `n` calls to `alloc` will not allocate, and later chunks double from `n`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let a = Arena::with_capacity(8);
+  /// let first = a.alloc(0) as *mut i32;
+  /// for i in 1..4 {
+  ///   assert_eq!(a.alloc(i) as *mut i32, first.wrapping_add(i as usize));
+  /// }
+  /// // the slice fits in the remaining capacity, so the first chunk is kept
+  /// assert_eq!(a.alloc_slice(vec![4, 5, 6]).as_mut_ptr(), first.wrapping_add(4));
+  /// // exactly 8 elements, still in one chunk
+  /// assert_eq!(a.alloc(7) as *mut i32, first.wrapping_add(7));
+  /// ```
+  #[inline]
+  pub fn with_capacity(n: usize) -> Self { Self::from_vec(Vec::with_capacity(n)) }
+
+  /// Construct a new arena that takes ownership of `v`'s buffer as its first chunk.
+  ///
+  /// The elements of `v` become the first elements of the arena without being copied or moved,
+  /// and the spare capacity of `v` is used by later allocations.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let mut v = Vec::with_capacity(5);
+  /// v.extend([1, 2, 3]);
+  /// let p = v.as_ptr();
+  /// let mut a = Arena::from_vec(v);
+  /// assert_eq!(a.len(), 3);
+  /// assert_eq!(a.alloc(4) as *const i32, p.wrapping_add(3));
+  /// assert_eq!(a.chunks().next().unwrap().as_ptr(), p);
+  /// assert!(a.into_vec().into_iter().eq(1..5));
+  ///
+  /// let a = Arena::from_vec(Vec::new());
+  /// assert_eq!(a.alloc_slice(vec![1, 2]), &[1, 2]);
+  /// ```
+  #[inline]
+  pub fn from_vec(v: Vec<T>) -> Self {
+    Self(UnsafeCell::new(Inner {
+      committed: Committed(Vec::new()), end: v.capacity(), cur: v, rest: Vec::new(), ends: Vec::new(),
+      tails: Tails::new(), id: next_id(),
+    }))
   }
 
   /// Allocates a value in the arena, and returns a mutable reference to it.
@@ -41,43 +113,2220 @@ impl<T> Arena<T> {
   /// otherwise it is impossible for arena to allocate more than one object.
   #[inline]
   pub fn alloc(&self, t: T) -> &mut T {
+    match self.try_alloc(t) { Ok(x) => x, Err(e) => e.raise() }
+  }
+
+  /// Allocates a value in the arena, and returns a mutable reference to it,
+  /// or an error containing the value if a new chunk can't be allocated.
+  ///
+  /// If `t` doesn't fit in the current chunk and the next one can't be allocated, `t` is given back in the error,
+  /// and the arena is left untouched.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let a = Arena::new();
+  /// assert_eq!(a.try_alloc(1).ok(), Some(&mut 1));
+  /// ```
+  #[inline]
+  pub fn try_alloc(&self, t: T) -> Result<&mut T, AllocError<T>> {
     unsafe {
-      let Inner { cur, rest } = &mut *self.0.get();
-      if cur.len() == cur.capacity() {
-        let cap = cur.len().checked_shl(1).expect("capacity overflow");
-        let old = mem::replace(cur, Vec::with_capacity(cap));
-        rest.push(old);
-      }
-      let len = cur.len();
-      let last = cur.as_mut_ptr().add(len);
-      cur.set_len(len + 1);
+      let last = match (*self.0.get()).try_claim(1) { Ok(p) => p, Err(e) => return Err(e.with(t)) };
       last.write(t);
+      Ok(&mut *last)
+    }
+  }
+
+  /// Allocates the value returned by `f` in the arena, and returns a mutable reference to it.
+  ///
+  /// The slot is reserved before calling `f`, so that the value can be constructed directly in it,
+  /// instead of being moved from the stack. If `f` panics, nothing is allocated.
+  ///
+  /// `f` may itself allocate from the arena. The slot is then counted first, so it comes before the values allocated
+  /// by `f` in allocation order, and they are allocated after it in the same chunk. If `f` panics after allocating,
+  /// these values are leaked: they are no longer counted, and are never dropped, but their slots are never reused.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let a = Arena::new();
+  /// let x = a.alloc_with(|| [0u64; 1024]);
+  /// assert_eq!(x.len(), 1024);
+  /// let y = a.alloc_with(|| { a.alloc([1; 1024]); [2; 1024] });
+  /// assert_eq!((x[0], y[0], a.len()), (0, 2, 3));
+  /// assert!(std::panic::catch_unwind(|| { Arena::new().alloc_with(|| -> [u64; 1024] { panic!() }); }).is_err());
+  /// ```
+  ///
+  /// Values allocated by `f` fill the current chunk like any other:
+  ///
+  /// ```
+  /// use arena::Arena;
+  /// use std::panic::{self, AssertUnwindSafe};
+  ///
+  /// let mut a = Arena::<u32>::with_capacity(1024);
+  /// for _ in 0..1000 { a.alloc_with(|| { a.alloc(1); 2 }); }
+  /// assert_eq!((a.chunk_count(), a.allocated_bytes()), (2, (1024 + 2048) * 4));
+  /// assert!(a.iter_mut().map(|x| *x).eq([2, 1].iter().copied().cycle().take(2000)));
+  ///
+  /// let r = panic::catch_unwind(AssertUnwindSafe(|| { a.alloc_with(|| { a.alloc(3); panic!() }); }));
+  /// assert!(r.is_err());
+  /// assert_eq!(a.len(), 2000);
+  /// a.alloc(4);
+  /// assert_eq!(a.get(2000), Some(&4));
+  /// ```
+  #[inline]
+  pub fn alloc_with(&self, f: impl FnOnce() -> T) -> &mut T {
+    let r = ReserveSlot::new(&self.0);
+    unsafe {
+      r.ptr().write(f());
+      r.finish()
+    }
+  }
+
+  /// Allocates the value returned by `f` in the arena, and returns a mutable reference to it.
+  ///
+  /// `f` is given the slot the value will be written to, so the value can refer to itself, in the spirit of
+  /// `Rc::new_cyclic`. The address of the slot must not be dereferenced before `alloc_cyclic` returns,
+  /// and afterwards it is subject to the usual aliasing rules with the returned reference.
+  /// Like `alloc_with`, `f` may itself allocate from the arena, and if it panics, nothing is allocated.
+  /// The address of the slot doesn't change when `f` allocates.
+  ///
+  /// ## Example
+  ///
+  /// A circular linked list of three nodes:
+  ///
+  /// ```
+  /// use arena::Arena;
+  /// use std::ptr::NonNull;
+  ///
+  /// struct Node { val: i32, next: NonNull<Node> }
+  ///
+  /// let a = Arena::new();
+  /// let first = a.alloc_cyclic(|slot| {
+  ///   let third = a.alloc(Node { val: 3, next: slot.as_ptr() });
+  ///   let second = a.alloc(Node { val: 2, next: NonNull::from(third) });
+  ///   Node { val: 1, next: NonNull::from(second) }
+  /// });
+  /// let first = NonNull::from(first);
+  /// let mut p = first;
+  /// for i in 0..6 {
+  ///   let node = unsafe { p.as_ref() };
+  ///   assert_eq!(node.val, i % 3 + 1);
+  ///   p = node.next;
+  /// }
+  /// assert_eq!(p, first);
+  ///
+  /// // a node pointing to itself
+  /// let x = a.alloc_cyclic(|slot| Node { val: 0, next: slot.as_ptr() }) as *mut Node;
+  /// assert_eq!(unsafe { (*x).next.as_ptr() }, x);
+  /// ```
+  #[inline]
+  pub fn alloc_cyclic(&self, f: impl FnOnce(&ArenaSlot<T>) -> T) -> &mut T {
+    let r = ReserveSlot::new(&self.0);
+    let slot = ArenaSlot(unsafe { NonNull::new_unchecked(r.ptr()) });
+    unsafe {
+      r.ptr().write(f(&slot));
+      r.finish()
+    }
+  }
+
+  /// Allocates a value in the arena, and returns a pinned mutable reference to it.
+  ///
+  /// Elements of an arena are never moved to make room for others, and they are dropped in place when the arena
+  /// is dropped, cleared, or truncated, so the pinning guarantee holds, even for `!Unpin` types such as
+  /// self-referential futures or intrusive list nodes. Methods that would let a pinned element be moved,
+  /// like `iter_mut` or `into_vec`, require `T: Unpin`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  /// use std::{marker::PhantomPinned, pin::Pin};
+  ///
+  /// struct Node { next: *const Node, _pin: PhantomPinned }
+  ///
+  /// let mut a = Arena::new();
+  /// let x: Pin<&mut Node> = a.alloc_pin(Node { next: std::ptr::null(), _pin: PhantomPinned });
+  /// let p = &*x as *const Node;
+  /// unsafe { x.get_unchecked_mut().next = p; }
+  /// assert_eq!(a.first().unwrap().next, p);
+  /// a.clear();
+  /// ```
+  ///
+  /// Pinned elements can't be moved out of the arena:
+  ///
+  /// ```compile_fail
+  /// use arena::Arena;
+  /// use std::marker::PhantomPinned;
+  ///
+  /// let a = Arena::new();
+  /// a.alloc_pin(PhantomPinned);
+  /// a.into_vec();
+  /// ```
+  #[inline]
+  pub fn alloc_pin(&self, t: T) -> Pin<&mut T> {
+    unsafe { Pin::new_unchecked(self.alloc(t)) }
+  }
+
+  /// Allocates a value in the arena, and returns an `ArenaBox` owning it, which drops the value when it is dropped.
+  ///
+  /// The value is stored in a slot reserved by `alloc_uninit`, so the arena never drops it, and it is not counted by `len`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  /// use std::rc::Rc;
+  ///
+  /// let r = Rc::new(());
+  /// let a = Arena::new();
+  /// let b = a.alloc_boxed(r.clone());
+  /// a.alloc(r.clone());
+  /// assert_eq!(Rc::strong_count(&b), 3);
+  /// drop(b);
+  /// assert_eq!((Rc::strong_count(&r), a.len()), (2, 1));
+  /// drop(a);
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  /// ```
+  #[inline]
+  pub fn alloc_boxed(&self, t: T) -> ArenaBox<'_, T> { ArenaBox::new(self.alloc_uninit(), t) }
+
+  /// Allocates an array in the arena, and returns a mutable reference to it.
+  ///
+  /// Unlike `alloc_slice(vec![...])`, this doesn't allocate a temporary `Vec`, and the array type is preserved.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let a = Arena::new();
+  /// let x: &mut [i32; 3] = a.alloc_array([1, 2, 3]);
+  /// assert_eq!(x, &[1, 2, 3]);
+  /// assert_eq!(a.alloc_array([]), &[]);
+  /// assert_eq!(a.len(), 3);
+  ///
+  /// let a = Arena::new();
+  /// let y = a.alloc_array([String::from("a"), String::from("b")]);
+  /// assert_eq!(y, &["a", "b"]);
+  /// ```
+  #[inline]
+  pub fn alloc_array<const N: usize>(&self, arr: [T; N]) -> &mut [T; N] {
+    unsafe {
+      let last = (*self.0.get()).claim(N) as *mut [T; N];
+      last.write(arr);
       &mut *last
     }
   }
 
+  /// Allocates an array in the arena, the `i`-th element of which is `f(i)`, and returns a mutable reference to it.
+  ///
+  /// The elements are written directly into the chunk, without building the array on the stack.
+  /// If `f` panics, the elements produced so far are owned by the arena and dropped with it.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  /// use std::{panic::{self, AssertUnwindSafe}, rc::Rc};
+  ///
+  /// let a = Arena::new();
+  /// a.alloc(0);
+  /// let x: &mut [usize; 4] = a.alloc_array_from_fn(|i| i * i); // needs a new chunk
+  /// assert_eq!(x, &[0, 1, 4, 9]);
+  /// let y: &mut [usize; 0] = a.alloc_array_from_fn(|_| unreachable!());
+  /// assert_eq!(y, &[]);
+  ///
+  /// let r = Rc::new(());
+  /// let a = Arena::new();
+  /// let res = panic::catch_unwind(AssertUnwindSafe(|| {
+  ///   a.alloc_array_from_fn::<3>(|i| if i == 2 { panic!() } else { r.clone() });
+  /// }));
+  /// assert!(res.is_err());
+  /// assert_eq!((Rc::strong_count(&r), a.len()), (3, 2));
+  /// drop(a);
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  /// ```
+  pub fn alloc_array_from_fn<const N: usize>(&self, mut f: impl FnMut(usize) -> T) -> &mut [T; N] {
+    let mut r = Reserve::new(&self.0, N);
+    for i in 0..N { r.push(f(i)); }
+    let s = r.finish();
+    debug_assert_eq!(s.len(), N);
+    unsafe { &mut *(s.as_mut_ptr() as *mut [T; N]) }
+  }
+
+  /// Reserves a slot in the arena, and returns a mutable reference to it, uninitialized.
+  ///
+  /// Just like `MaybeUninit<T>` itself, the arena never knows whether the slot has been initialized,
+  /// so it never drops its content, unless it is committed by `assume_init_slice`. Alternatively, a value
+  /// can be dropped in place by the caller, or constructed with `alloc_with` instead. The slot is not counted by `len`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  /// use std::{mem::MaybeUninit, rc::Rc};
+  ///
+  /// let a = Arena::new();
+  /// let slot: &mut MaybeUninit<[u8; 4]> = a.alloc_uninit();
+  /// let x = slot.write(*b"abcd");
+  /// let y = a.alloc(*b"efgh");
+  /// assert_eq!((&*x, &*y), (b"abcd", b"efgh"));
+  /// assert_eq!(a.len(), 1);
+  ///
+  /// let r = Rc::new(());
+  /// let a = Arena::new();
+  /// a.alloc_uninit(); // abandoned
+  /// a.alloc_uninit().write(r.clone()); // leaked
+  /// a.alloc(r.clone());
+  /// drop(a);
+  /// assert_eq!(Rc::strong_count(&r), 2);
+  /// # // release the leaked clone, so that Miri doesn't report it
+  /// # unsafe { Rc::decrement_strong_count(Rc::as_ptr(&r)); }
+  /// ```
+  #[inline]
+  pub fn alloc_uninit(&self) -> &mut MaybeUninit<T> {
+    unsafe {
+      let inner = &mut *self.0.get();
+      if inner.spare() == 0 {
+        inner.grow(1);
+      }
+      inner.end -= 1;
+      &mut *(inner.cur.as_mut_ptr().add(inner.end) as *mut MaybeUninit<T>)
+    }
+  }
+
+  /// Reserves `n` contiguous slots in the arena, and returns a mutable reference to them, uninitialized.
+  ///
+  /// Like `alloc_uninit`, the arena never drops their content, unless the slice is committed by `assume_init_slice`.
+  /// The slots are not counted by `len`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  /// use std::rc::Rc;
+  ///
+  /// let r = Rc::new(());
+  /// let a = Arena::new();
+  /// a.alloc_uninit_slice(5); // never committed
+  /// let s = a.alloc_uninit_slice(3);
+  /// for x in s.iter_mut() { x.write(r.clone()); }
+  /// let s = unsafe { a.assume_init_slice(s) };
+  /// assert_eq!(s.len(), 3);
+  /// assert_eq!(a.alloc_uninit_slice(0).len(), 0);
+  /// assert_eq!(Rc::strong_count(&r), 4);
+  /// drop(a);
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  /// ```
+  #[inline]
+  pub fn alloc_uninit_slice(&self, n: usize) -> &mut [MaybeUninit<T>] {
+    unsafe {
+      let inner = &mut *self.0.get();
+      if inner.spare() < n {
+        inner.grow(n);
+      }
+      inner.end -= n;
+      slice::from_raw_parts_mut(inner.cur.as_mut_ptr().add(inner.end) as *mut MaybeUninit<T>, n)
+    }
+  }
+
+  /// Commits a slice reserved by `alloc_uninit_slice` or `alloc_uninit`, so that the arena drops its elements
+  /// when it is dropped (or consumed), and returns a mutable reference to its initialized content.
+  ///
+  /// Committed elements are not counted by `len` or visited by the iterators.
+  ///
+  /// # Safety
+  ///
+  /// `slice` must have been reserved from this arena, it must be fully initialized, and it must not be committed twice.
+  pub unsafe fn assume_init_slice<'a>(&'a self, slice: &'a mut [MaybeUninit<T>]) -> &'a mut [T] {
+    let p = slice as *mut [MaybeUninit<T>] as *mut [T];
+    (*self.0.get()).committed.0.push(p);
+    &mut *p
+  }
+
   /// Allocates a slice in the arena, and returns a mutable reference to it.
+  ///
+  /// If `t` doesn't fit in the current chunk, is larger than it, and takes at least 64 KiB, its buffer is adopted
+  /// as a chunk of the arena instead of being copied. If the current chunk is empty, it stays the current chunk;
+  /// otherwise it is retired and replaced by a chunk of the same capacity, to keep elements in allocation order.
+  /// Slots reserved by `alloc_uninit` count as non-empty here.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let a = Arena::new();
+  /// let v = vec![0u64; 10000];
+  /// let p = v.as_ptr();
+  /// assert_eq!(a.alloc_slice(v).as_ptr(), p);
+  /// // the current chunk is still the first one
+  /// assert_eq!((a.chunk_count(), a.allocated_bytes()), (2, 80008));
+  /// a.alloc(1);
+  /// a.alloc_slice(vec![2; 10000]);
+  /// a.alloc(3);
+  /// assert_eq!(a.chunk_count(), 4);
+  /// assert_eq!(a.into_vec().into_iter().skip(10000).collect::<Vec<_>>(), [&[1][..], &[2; 10000], &[3]].concat());
+  /// ```
   #[inline]
   pub fn alloc_slice(&self, t: Vec<T>) -> &mut [T] {
+    match self.try_alloc_slice(t) { Ok(x) => x, Err(e) => e.raise() }
+  }
+
+  /// Allocates a slice in the arena, and returns a mutable reference to it,
+  /// or an error containing the `Vec` if a new chunk can't be allocated.
+  ///
+  /// The error is returned if the capacity of the chunk needed for the slice overflows `usize`, or if the chunk
+  /// can't be allocated. Either way the arena is left untouched, and the `Vec` is given back with all its elements.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let a = Arena::new();
+  /// assert_eq!(a.try_alloc_slice(vec![1, 2]).unwrap(), &[1, 2]);
+  ///
+  /// // the capacity of a new chunk would overflow `usize`
+  /// let a = Arena::new();
+  /// a.alloc(());
+  /// let e = a.try_alloc_slice(vec![(); usize::MAX]).unwrap_err();
+  /// assert_eq!(e.layout(), None);
+  /// assert_eq!(e.into_inner().len(), usize::MAX);
+  /// assert_eq!(a.len(), 1);
+  /// ```
+  #[inline]
+  pub fn try_alloc_slice(&self, t: Vec<T>) -> Result<&mut [T], AllocError<Vec<T>>> {
+    unsafe { self.try_alloc_slice_ptr(t).map(|p| &mut *p) }
+  }
+
+  /// Allocates a value in the arena, and returns a pointer to it, without creating a reference to it.
+  ///
+  /// The pointer is valid for reads and writes until the arena is dropped, or any method taking `&mut self` is called,
+  /// since methods like `make_contiguous`, `shrink_to_fit` or `append` may move the elements. This makes it suitable
+  /// to be handed to foreign code. As usual, it must not be used while a reference to the same element is alive,
+  /// e.g. one created from the pointer itself.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// // stands for a C function storing the pointer and writing through it later
+  /// unsafe fn c_side(p: *mut i32, v: i32) { *p = v; }
+  ///
+  /// let a = Arena::new();
+  /// let p = a.alloc_raw(1);
+  /// let s = a.alloc_slice_raw(vec![2, 3]);
+  /// a.alloc(4);
+  /// unsafe {
+  ///   c_side(p.as_ptr(), 10);
+  ///   c_side(s.as_ptr() as *mut i32, 20);
+  ///   assert_eq!(*p.as_ref(), 10);
+  ///   assert_eq!(s.as_ref(), &[20, 3]);
+  /// }
+  /// assert_eq!(a.into_vec(), [10, 20, 3, 4]);
+  /// ```
+  #[inline]
+  pub fn alloc_raw(&self, t: T) -> NonNull<T> {
     unsafe {
-      let Inner { cur, rest } = &mut *self.0.get();
-      if cur.capacity() - cur.len() < t.len() {
-        let cap = cur.len().checked_shl(1).expect("capacity overflow");
-        let old = mem::replace(cur, Vec::with_capacity(cap.max(t.len())));
-        rest.push(old);
-      }
-      let len = cur.len();
-      let last = cur.as_mut_ptr().add(len);
-      let (ptr, additional, cap) = t.into_raw_parts();
-      cur.set_len(len + additional);
-      last.copy_from_nonoverlapping(ptr, additional);
-      let _ = Vec::from_raw_parts(ptr, 0, cap); // deallocate Vec memory without calling element destructor
-      slice::from_raw_parts_mut(last, additional)
+      let last = (*self.0.get()).claim(1);
+      last.write(t);
+      NonNull::new_unchecked(last)
     }
   }
-}
 
-impl<T> Default for Arena<T> {
-  /// Equivalent to calling `Arena::<T>::new()`.
-  fn default() -> Self { Self::new() }
-}
\ No newline at end of file
+  /// Allocates a slice in the arena, and returns a pointer to it, without creating a reference to it.
+  ///
+  /// See `alloc_raw` for details.
+  #[inline]
+  pub fn alloc_slice_raw(&self, t: Vec<T>) -> NonNull<[T]> {
+    match unsafe { self.try_alloc_slice_ptr(t) } { Ok(p) => unsafe { NonNull::new_unchecked(p) }, Err(e) => e.raise() }
+  }
+
+  /// Implementation of `try_alloc_slice`, returning a pointer.
+  #[inline]
+  unsafe fn try_alloc_slice_ptr(&self, t: Vec<T>) -> Result<*mut [T], AllocError<Vec<T>>> {
+    let inner = &mut *self.0.get();
+    inner.settle();
+    if t.len() > inner.spare() && t.len() >= inner.cur.capacity() && t.len() * mem::size_of::<T>() >= ADOPT_BYTES {
+      return match inner.try_prepare_adopt() {
+        Ok(()) => {
+          inner.retire(t);
+          let last = inner.rest.last_mut().unwrap();
+          Ok(ptr::slice_from_raw_parts_mut(last.as_mut_ptr(), last.len()))
+        }
+        Err(e) => Err(e.with(t)),
+      };
+    }
+    let last = match inner.try_claim(t.len()) { Ok(p) => p, Err(e) => return Err(e.with(t)) };
+    let (ptr, additional, cap) = t.into_raw_parts();
+    last.copy_from_nonoverlapping(ptr, additional);
+    let _ = Vec::from_raw_parts(ptr, 0, cap); // deallocate Vec memory without calling element destructor
+    Ok(ptr::slice_from_raw_parts_mut(last, additional))
+  }
+
+  /// Allocates a value in the arena, and returns its index together with a mutable reference to it.
+  ///
+  /// The index is the position of the value in allocation order, to be used with `get` and `get_mut`. It doesn't
+  /// depend on the layout of chunks, and stays valid until the arena is cleared or truncated before it.
+  /// Storing indices instead of references keeps lifetimes out of data structures, and an index stored as `u32`
+  /// takes 4 bytes, half the size of a reference on 64-bit targets.
+  ///
+  /// ## Example
+  ///
+  /// A tree whose nodes refer to their parents by index:
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// struct Node { parent: Option<u32>, depth: u32 }
+  ///
+  /// let mut a = Arena::new();
+  /// let (root, _) = a.alloc_idx(Node { parent: None, depth: 0 });
+  /// let mut leaf = root as u32;
+  /// for depth in 1..100 {
+  ///   let (i, _) = a.alloc_idx(Node { parent: Some(leaf), depth });
+  ///   a.alloc_slice(vec![Node { parent: Some(leaf), depth }, Node { parent: Some(leaf), depth }]);
+  ///   leaf = i as u32;
+  /// }
+  /// // walk back to the root
+  /// let mut n = 0;
+  /// while let Some(p) = a.get(leaf as usize).unwrap().parent {
+  ///   assert_eq!(a.get(p as usize).unwrap().depth + 1, a.get(leaf as usize).unwrap().depth);
+  ///   leaf = p;
+  ///   n += 1;
+  /// }
+  /// assert_eq!((leaf, n), (0, 99));
+  /// ```
+  ///
+  /// Values allocated while `alloc_extend` or `alloc_with` runs user code get the right indices:
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let mut a = Arena::new();
+  /// a.alloc(0);
+  /// let mut idx = Vec::new();
+  /// a.alloc_extend((0..2).map(|i| { idx.push(a.alloc_idx(i + 1).0); i + 100 }));
+  /// a.alloc_with(|| { idx.push(a.alloc_idx(3).0); 200 });
+  /// assert_eq!(idx, [1, 2, 6]);
+  /// assert_eq!(idx.iter().map(|&i| *a.get(i).unwrap()).collect::<Vec<_>>(), [1, 2, 3]);
+  /// assert_eq!(a.into_vec(), [0, 1, 2, 100, 101, 200, 3]);
+  /// ```
+  #[inline]
+  pub fn alloc_idx(&self, t: T) -> (usize, &mut T) {
+    let x = self.alloc(t);
+    (self.len() - 1, x)
+  }
+
+  /// Moves all elements of `v` into the arena as a contiguous slice, and returns a mutable reference to it.
+  ///
+  /// `v` is left empty, but keeps its capacity, so a scratch buffer can be reused without reallocating.
+  /// The current chunk is retired if the slice doesn't fit in it, like `alloc_slice`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  /// use std::rc::Rc;
+  ///
+  /// let r = Rc::new(());
+  /// let a = Arena::new();
+  /// let mut scratch = Vec::with_capacity(10);
+  /// let buf = scratch.as_ptr();
+  /// for i in 0..1000 {
+  ///   scratch.extend((0..i % 10).map(|j| (j, r.clone())));
+  ///   let x = a.alloc_slice_drain(&mut scratch);
+  ///   assert!(x.iter().map(|d| d.0).eq(0..i % 10));
+  ///   assert!(scratch.is_empty());
+  /// }
+  /// assert_eq!((scratch.as_ptr(), scratch.capacity()), (buf, 10));
+  /// drop(scratch);
+  /// assert_eq!(Rc::strong_count(&r), 4501);
+  /// drop(a);
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  /// ```
+  #[inline]
+  pub fn alloc_slice_drain(&self, v: &mut Vec<T>) -> &mut [T] {
+    unsafe {
+      let n = v.len();
+      let last = (*self.0.get()).claim(n);
+      last.copy_from_nonoverlapping(v.as_ptr(), n);
+      v.set_len(0);
+      slice::from_raw_parts_mut(last, n)
+    }
+  }
+
+  /// Allocates a boxed slice in the arena, and returns a mutable reference to it.
+  ///
+  /// The box is converted to a `Vec` without copying, so this behaves exactly like `alloc_slice`:
+  /// the elements are moved into the current chunk, or the allocation of a large box is adopted as a chunk.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  /// use std::rc::Rc;
+  ///
+  /// let r = Rc::new(());
+  /// let a = Arena::new();
+  /// assert_eq!(a.alloc_boxed_slice(Box::new([])).len(), 0);
+  /// assert_eq!(a.alloc_boxed_slice(vec![r.clone(), r.clone()].into_boxed_slice()).len(), 2);
+  /// assert_eq!(Rc::strong_count(&r), 3);
+  /// drop(a);
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  ///
+  /// let a = Arena::new();
+  /// let b: Box<[u64]> = vec![1; 10000].into_boxed_slice();
+  /// let p = b.as_ptr();
+  /// assert_eq!(a.alloc_boxed_slice(b).as_ptr(), p);
+  /// ```
+  #[inline]
+  pub fn alloc_boxed_slice(&self, b: Box<[T]>) -> &mut [T] { self.alloc_slice(b.into_vec()) }
+
+  /// Allocates a slice in the arena, and returns the index of its first element together with a mutable reference to it.
+  ///
+  /// See `alloc_idx` for details.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let mut a = Arena::new();
+  /// a.alloc(0);
+  /// assert_eq!(a.alloc_slice_idx(vec![1, 2, 3]).0, 1);
+  /// assert_eq!(a.alloc_slice_idx(vec![4; 100000]).0, 4);
+  /// assert_eq!(a.alloc_idx(5).0, 100004);
+  /// assert_eq!(a.get(3), Some(&3));
+  /// assert_eq!(a.get(100003), Some(&4));
+  /// assert_eq!(a.get(100004), Some(&5));
+  /// ```
+  #[inline]
+  pub fn alloc_slice_idx(&self, t: Vec<T>) -> (usize, &mut [T]) {
+    let x = self.alloc_slice(t);
+    (self.len() - x.len(), x)
+  }
+
+  /// Allocates all items of an iterator in the arena as a contiguous slice, and returns a mutable reference to it.
+  ///
+  /// The items are written directly into the current chunk, without building a temporary `Vec`.
+  /// Space for `size_hint().0` items is reserved up front; if the iterator yields more than the current
+  /// chunk can hold, the items written so far are moved to a new chunk.
+  ///
+  /// The iterator may itself allocate from the arena. The items written so far are then moved out of the way,
+  /// and the slice is allocated when the iterator is exhausted, so it comes after the elements the iterator allocated,
+  /// both in allocation order and in the current chunk.
+  ///
+  /// If the iterator panics, the items it has already yielded are owned by the arena and dropped with it.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let a = Arena::new();
+  /// a.alloc(0);
+  /// let x = a.alloc_extend(1..4);
+  /// assert_eq!(x, &[1, 2, 3]);
+  /// // the size hint of `filter` is useless, but items remain contiguous
+  /// let y = a.alloc_extend((0..100).filter(|x| x % 2 == 0));
+  /// assert!(y.iter().copied().eq((0..100).step_by(2)));
+  /// assert_eq!(a.alloc_extend(None), &[]);
+  /// assert_eq!(a.len(), 54);
+  /// ```
+  ///
+  /// Items yielded before a panic are dropped exactly once:
+  ///
+  /// ```
+  /// use arena::Arena;
+  /// use std::{panic::{self, AssertUnwindSafe}, rc::Rc};
+  ///
+  /// let r = Rc::new(());
+  /// let a = Arena::new();
+  /// let res = panic::catch_unwind(AssertUnwindSafe(|| {
+  ///   a.alloc_extend((0..10).map(|i| if i == 5 { panic!() } else { r.clone() }));
+  /// }));
+  /// assert!(res.is_err());
+  /// assert_eq!(Rc::strong_count(&r), 6);
+  /// a.alloc(r.clone());
+  /// assert_eq!(a.len(), 6);
+  /// drop(a);
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  /// ```
+  ///
+  /// An iterator allocating from the arena:
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let a = Arena::with_capacity(16);
+  /// let x = a.alloc_extend((0..4).map(|i| *a.alloc(i * 10) + 1));
+  /// assert_eq!(x, &[1, 11, 21, 31]);
+  /// assert_eq!(a.chunk_count(), 1);
+  /// let y = a.alloc_extend((0..20).map(|i| if i % 5 == 0 { *a.alloc(-i) } else { i }));
+  /// assert!(y.iter().copied().eq((0..20).map(|i| if i % 5 == 0 { -i } else { i })));
+  /// assert_eq!(a.len(), 32);
+  /// assert_eq!(a.into_vec()[..12], [0, 10, 20, 30, 1, 11, 21, 31, 0, -5, -10, -15]);
+  /// ```
+  pub fn alloc_extend(&self, iter: impl IntoIterator<Item = T>) -> &mut [T] {
+    let iter = iter.into_iter();
+    let mut r = Reserve::new(&self.0, iter.size_hint().0);
+    for t in iter { r.push(t); }
+    r.finish()
+  }
+
+  /// Allocates all items of an `ExactSizeIterator` in the arena as a contiguous slice, and returns a mutable reference to it.
+  ///
+  /// Space for `iter.len()` items is reserved once, and the items are written directly into it.
+  /// The returned slice always contains exactly the items the iterator yielded: if it yields fewer items
+  /// than it claims, the slice is shorter, and if it yields more, they are moved to a new chunk like `alloc_extend`.
+  /// Uninitialized memory is never exposed.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let a = Arena::new();
+  /// let src = [1, 2, 3];
+  /// assert_eq!(a.alloc_from_iter_exact(src.iter().map(|x| x * 10)), &[10, 20, 30]);
+  ///
+  /// // an iterator lying about its length
+  /// struct Liar(usize);
+  /// impl Iterator for Liar {
+  ///   type Item = usize;
+  ///   fn next(&mut self) -> Option<usize> { self.0 = self.0.checked_sub(1)?; Some(self.0) }
+  ///   fn size_hint(&self) -> (usize, Option<usize>) { (5, Some(5)) }
+  /// }
+  /// impl ExactSizeIterator for Liar {}
+  /// assert_eq!(a.alloc_from_iter_exact(Liar(2)), &[1, 0]);
+  /// assert_eq!(a.alloc_from_iter_exact(Liar(8)), &[7, 6, 5, 4, 3, 2, 1, 0]);
+  /// assert_eq!(a.len(), 13);
+  /// ```
+  pub fn alloc_from_iter_exact<I: ExactSizeIterator<Item = T>>(&self, iter: I) -> &mut [T] {
+    let mut r = Reserve::new(&self.0, iter.len());
+    for t in iter { r.push(t); }
+    r.finish()
+  }
+
+  /// Allocates a slice of `n` elements in the arena, the `i`-th of which is `f(i)`, and returns a mutable reference to it.
+  ///
+  /// If `f` panics, the elements produced so far are owned by the arena and dropped with it.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let a = Arena::new();
+  /// assert_eq!(a.alloc_slice_fill_with(4, |i| i * i), &[0, 1, 4, 9]);
+  /// assert_eq!(a.alloc_slice_fill_with(0, |i| i), &[]);
+  /// assert_eq!(a.len(), 4);
+  /// ```
+  pub fn alloc_slice_fill_with(&self, n: usize, mut f: impl FnMut(usize) -> T) -> &mut [T] {
+    let mut r = Reserve::new(&self.0, n);
+    for i in 0..n { r.push(f(i)); }
+    r.finish()
+  }
+
+  /// Reserves capacity for at least `additional` more elements in the current chunk.
+  ///
+  /// After this, the next `additional` elements allocated by `alloc` (or a slice of them) are placed in the current
+  /// chunk without allocating. If there is not enough room, the current chunk is retired and a new one is allocated,
+  /// at least twice as large as it, as if growing normally.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let a = Arena::new();
+  /// a.alloc(0);
+  /// a.reserve(10);
+  /// let x = a.alloc(1) as *mut i32;
+  /// for i in 1..10 { assert_eq!(a.alloc(1) as *mut i32, x.wrapping_add(i)); }
+  /// a.reserve(0);
+  /// a.reserve(5);
+  /// let y = a.alloc_slice(vec![2; 5]).as_mut_ptr();
+  /// assert_ne!(y, x.wrapping_add(10));
+  /// ```
+  #[inline]
+  pub fn reserve(&self, additional: usize) {
+    let inner = unsafe { &mut *self.0.get() };
+    if inner.spare() < additional {
+      inner.grow(additional);
+    }
+  }
+
+  /// Tries to reserve capacity for at least `additional` more elements in the current chunk, like `reserve`.
+  ///
+  /// On failure, the current chunk is kept, so the spare capacity reserved before is still there.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let a = Arena::<u64>::new();
+  /// a.alloc(0);
+  /// a.try_reserve(10).unwrap();
+  /// assert!(a.spare_capacity() >= 10);
+  /// assert!(a.try_reserve(usize::MAX).is_err());
+  /// assert!(a.spare_capacity() >= 10);
+  /// a.alloc(1);
+  /// assert_eq!(a.chunk_count(), 2);
+  /// ```
+  #[inline]
+  pub fn try_reserve(&self, additional: usize) -> Result<(), AllocError> {
+    let inner = unsafe { &mut *self.0.get() };
+    if inner.spare() < additional {
+      inner.try_grow(additional)?;
+    }
+    Ok(())
+  }
+
+  /// Returns the number of elements that can be allocated in the current chunk without allocating a new one.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let a = Arena::with_capacity(4);
+  /// assert_eq!(a.spare_capacity(), 4);
+  /// a.alloc(0);
+  /// a.alloc_uninit();
+  /// assert_eq!(a.spare_capacity(), 2);
+  /// ```
+  #[inline]
+  pub fn spare_capacity(&self) -> usize { unsafe { (*self.0.get()).spare() } }
+
+  /// Returns the number of elements allocated in the arena, including those allocated by `alloc_slice`.
+  ///
+  /// The elements of a slice being filled by `alloc_extend` and similar methods are counted when it is complete.
+  /// The slot being filled by `alloc_with` or `alloc_cyclic` is counted when it is written, or when `f` allocates.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let a = Arena::new();
+  /// assert!(a.is_empty());
+  /// a.alloc(0);
+  /// assert_eq!(a.len(), 1);
+  /// a.alloc_slice(vec![1, 2, 3]);
+  /// assert_eq!(a.len(), 4);
+  /// a.alloc(4);
+  /// assert_eq!(a.len(), 5);
+  /// a.alloc_slice(vec![5; 10]);
+  /// assert_eq!(a.len(), 15);
+  /// a.alloc_slice(vec![]);
+  /// assert_eq!(a.len(), 15);
+  /// for i in 0..20 { a.alloc(i); }
+  /// assert_eq!(a.len(), 35);
+  /// ```
+  #[inline]
+  pub fn len(&self) -> usize {
+    let inner = unsafe { &*self.0.get() };
+    inner.retired_len() + inner.cur.len()
+  }
+
+  /// Returns `true` if no element has been allocated in the arena.
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+  /// Returns an iterator over mutable references to all elements, in allocation order.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let mut a = Arena::with_capacity(8);
+  /// assert_eq!(a.iter_mut().next(), None);
+  /// a.alloc_slice(vec![0, 1, 2]);
+  /// assert!(a.iter_mut().map(|x| *x).eq(0..3));
+  ///
+  /// let mut a = Arena::new();
+  /// a.alloc(0);
+  /// a.alloc_slice(vec![1, 2, 3]);
+  /// for i in 4..10 { a.alloc(i); }
+  /// for x in a.iter_mut() { *x *= 2; }
+  /// assert_eq!(a.iter_mut().len(), 10);
+  /// assert!(a.iter_mut().map(|x| *x).eq((0..10).map(|x| x * 2)));
+  /// assert!(a.iter_mut().rev().map(|x| *x).eq((0..10).rev().map(|x| x * 2)));
+  /// ```
+  #[inline]
+  pub fn iter_mut(&mut self) -> IterMut<'_, T> where T: Unpin {
+    let len = self.len();
+    let Inner { cur, rest, .. } = self.0.get_mut();
+    IterMut { chunks: rest.iter_mut(), front: [].iter_mut(), back: cur.iter_mut(), len }
+  }
+
+  /// Returns a reference to the `i`-th allocated element, or `None` if `i >= len()`.
+  ///
+  /// Elements are indexed in allocation order, as yielded by `iter_mut`, and an index stays valid
+  /// until the arena is cleared or truncated, see `alloc_idx`. The chunk holding the element is found by a binary search.
+  ///
+  /// The returned reference may point to an element `alloc` has returned a mutable reference to,
+  /// which is why this takes `&mut self`. Use `indexed` to index the arena with `[]`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let mut a = Arena::new();
+  /// a.alloc(0);
+  /// a.alloc_slice(vec![1, 2, 3]);
+  /// a.alloc_slice((4..100).collect());
+  /// for i in 100..200 { a.alloc(i); }
+  /// assert!((0..200).all(|i| a.get(i) == Some(&i)));
+  /// assert_eq!(a.get(200), None);
+  /// *a.get_mut(150).unwrap() = 0;
+  /// assert_eq!(a.into_vec()[150], 0);
+  /// ```
+  #[inline]
+  pub fn get(&mut self, i: usize) -> Option<&T> { self.0.get_mut().get(i).map(|x| unsafe { &*x }) }
+
+  /// Returns a mutable reference to the `i`-th allocated element, or `None` if `i >= len()`.
+  ///
+  /// See `get` for details.
+  #[inline]
+  pub fn get_mut(&mut self, i: usize) -> Option<&mut T> where T: Unpin {
+    self.0.get_mut().get(i).map(|x| unsafe { &mut *x })
+  }
+
+  /// Returns a view of the arena implementing `Index<usize>` and `IndexMut<usize>`, like `get` and `get_mut`.
+  ///
+  /// The view borrows the arena mutably, so no reference returned by `alloc` is alive while it is indexed.
+  ///
+  /// ## Panics
+  ///
+  /// Indexing panics if the index is not less than `len()`, with both in the message.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let mut a = Arena::new();
+  /// a.alloc_slice(vec![1, 2]);
+  /// a.alloc(3);
+  /// let mut v = a.indexed();
+  /// v[2] += v[0] + v[1];
+  /// assert_eq!((v[2], v.len()), (6, 3));
+  /// ```
+  ///
+  /// ```should_panic
+  /// use arena::Arena;
+  ///
+  /// let mut a = Arena::new();
+  /// a.alloc(1);
+  /// // panics with "index 1 out of range for arena of length 1"
+  /// a.indexed()[1];
+  /// ```
+  #[inline]
+  pub fn indexed(&mut self) -> Indexed<'_, T> { Indexed(self) }
+
+  /// Returns `true` if `x` refers to an element allocated in this arena, including slices committed by `assume_init_slice`.
+  ///
+  /// This takes `O(chunk_count())` time, plus the number of committed slices. Always returns `false` for zero-sized types,
+  /// since they have no address to check.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let (a, b) = (Arena::new(), Arena::new());
+  /// let x = &*a.alloc_slice(vec![1, 2, 3]);
+  /// let y = &*b.alloc(1);
+  /// assert!(x.iter().all(|x| a.contains(x)));
+  /// assert!(!a.contains(y) && b.contains(y));
+  /// assert!(!a.contains(&1));
+  /// // not committed by `assume_init_slice`
+  /// let u = a.alloc_uninit().write(4);
+  /// assert!(!a.contains(u));
+  ///
+  /// // while `alloc_extend` runs the iterator, or `alloc_with` runs the closure
+  /// let s = a.alloc_extend((5..8).map(|i| {
+  ///   // the values allocated by `alloc_with` are counted, the slice not yet
+  ///   assert!(x.iter().all(|x| a.contains(x)) && a.len() == i - 2);
+  ///   a.alloc_with(|| { assert!(a.contains(&x[0]) && a.len() == i - 2); i })
+  /// }).map(|x| *x));
+  /// assert!(s.iter().all(|x| a.contains(x)) && a.len() == 9);
+  /// ```
+  pub fn contains(&self, x: &T) -> bool {
+    let inner = unsafe { &*self.0.get() };
+    let (addr, size) = (x as *const T as usize, mem::size_of::<T>());
+    let within = |p: *const T, len: usize| (p as usize..p as usize + len * size).contains(&addr);
+    inner.rest.iter().chain(iter::once(&inner.cur)).any(|c| within(c.as_ptr(), c.len()))
+      || inner.committed.0.iter().any(|&p| within(p as *const T, p.len()))
+  }
+
+  /// Returns the index of the element `x` refers to, as used by `get`, or `None` if it isn't allocated in this arena.
+  ///
+  /// Slices committed by `assume_init_slice` have no index. Like `contains`, this takes `O(chunk_count())` time,
+  /// and always returns `None` for zero-sized types.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let mut a = Arena::new();
+  /// let mut refs = Vec::new();
+  /// for i in 0..10 { refs.push(&*a.alloc(i)); }
+  /// refs.extend(a.alloc_slice((10..50).collect()).iter());
+  /// for i in 50..100 { refs.push(&*a.alloc(i)); }
+  /// assert!(refs.iter().enumerate().all(|(i, x)| a.position_of(x) == Some(i)));
+  /// assert_eq!(a.position_of(&0), None);
+  /// a.alloc_uninit();
+  /// for i in 0..a.len() {
+  ///   let x = a.get(i).unwrap() as *const i32;
+  ///   assert_eq!(a.position_of(unsafe { &*x }), Some(i));
+  /// }
+  /// ```
+  pub fn position_of(&self, x: &T) -> Option<usize> {
+    let inner = unsafe { &*self.0.get() };
+    let size = mem::size_of::<T>();
+    if size == 0 { return None; }
+    let addr = x as *const T as usize;
+    let offset = |c: &Vec<T>| addr.checked_sub(c.as_ptr() as usize).map(|d| d / size).filter(|&i| i < c.len());
+    for (k, c) in inner.rest.iter().enumerate() {
+      if let Some(i) = offset(c) { return Some(if k == 0 { 0 } else { inner.ends[k - 1] } + i); }
+    }
+    offset(&inner.cur).map(|i| inner.retired_len() + i)
+  }
+
+  /// Returns a reference to the first allocated element, or `None` if the arena is empty.
+  ///
+  /// This takes `&mut self`, like `get`, instead of `&self`: `alloc` returns a mutable reference to the first element,
+  /// which may still be alive, and must not be aliased by a shared one. The borrow checker rejects that by `&mut self`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let mut a = Arena::new();
+  /// assert_eq!(a.first(), None);
+  /// a.alloc_slice(vec![1, 2]);
+  /// assert_eq!(a.first(), Some(&1));
+  /// ```
+  ///
+  /// A reference returned by `alloc` can't be used together with it:
+  ///
+  /// ```compile_fail
+  /// use arena::Arena;
+  ///
+  /// let mut a = Arena::new();
+  /// let x = a.alloc(1);
+  /// let y = a.first().unwrap();
+  /// *x += *y;
+  /// ```
+  #[inline]
+  pub fn first(&mut self) -> Option<&T> { self.chunks().find_map(|c| c.first()) }
+
+  /// Returns a mutable reference to the first allocated element, or `None` if the arena is empty.
+  #[inline]
+  pub fn first_mut(&mut self) -> Option<&mut T> where T: Unpin {
+    self.chunks_mut().find_map(|c| c.first_mut())
+  }
+
+  /// Returns a mutable reference to the last allocated element, or `None` if the arena is empty.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let mut a = Arena::new();
+  /// assert_eq!(a.last_mut(), None);
+  /// a.alloc(1);
+  /// assert_eq!(a.last_mut(), Some(&mut 1));
+  /// // the current chunk is full, so this is allocated in a new chunk
+  /// a.alloc(2);
+  /// assert_eq!(a.chunk_count(), 2);
+  /// *a.last_mut().unwrap() += 1;
+  /// // the new current chunk is still empty, since the slice doesn't fit in it
+  /// a.alloc_slice(vec![4, 5, 6]);
+  /// a.reserve(10);
+  /// assert_eq!(a.chunk_count(), 4);
+  /// assert_eq!(a.last_mut(), Some(&mut 6));
+  /// assert_eq!(a.first_mut(), Some(&mut 1));
+  /// assert_eq!(a.into_vec(), [1, 3, 4, 5, 6]);
+  /// ```
+  #[inline]
+  pub fn last_mut(&mut self) -> Option<&mut T> where T: Unpin {
+    let Inner { cur, rest, .. } = self.0.get_mut();
+    match cur.last_mut() {
+      Some(x) => Some(x),
+      None => rest.iter_mut().rev().find_map(|c| c.last_mut()),
+    }
+  }
+
+  /// Calls `f` on each element, in allocation order.
+  ///
+  /// This is the recommended way to run a finalization pass over all objects allocated in the arena.
+  /// If `f` panics, the arena is left intact, and can still be used and dropped.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let mut a = Arena::new();
+  /// a.alloc(1);
+  /// a.alloc_slice(vec![2, 3]);
+  /// let mut sum = 0;
+  /// a.for_each(|x| sum += x);
+  /// assert_eq!(sum, 6);
+  /// ```
+  #[inline]
+  pub fn for_each(&mut self, mut f: impl FnMut(&T)) {
+    for c in self.chunks() { c.iter().for_each(&mut f); }
+  }
+
+  /// Calls `f` on a mutable reference to each element, in allocation order.
+  ///
+  /// See `for_each` for details.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  /// use std::panic::{self, AssertUnwindSafe};
+  ///
+  /// let mut a = Arena::new();
+  /// a.alloc(String::from("a"));
+  /// a.alloc_slice(vec![String::from("b"), String::from("c")]);
+  /// a.for_each_mut(|s| s.push('!'));
+  /// let r = panic::catch_unwind(AssertUnwindSafe(|| a.for_each_mut(|s| if s == "b!" { panic!() } else { s.push('?') })));
+  /// assert!(r.is_err());
+  /// a.alloc(String::from("d"));
+  /// assert_eq!(a.into_vec(), ["a!?", "b!", "c!", "d"]);
+  /// ```
+  #[inline]
+  pub fn for_each_mut(&mut self, mut f: impl FnMut(&mut T)) where T: Unpin {
+    for c in self.chunks_mut() { c.iter_mut().for_each(&mut f); }
+  }
+
+  /// Returns the total size in bytes of all chunks, i.e. the sum of their capacities times `size_of::<T>()`.
+  ///
+  /// The memory used to keep track of the chunks themselves is not included.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let a = Arena::<u32>::new();
+  /// assert_eq!((a.chunk_count(), a.allocated_bytes(), a.used_bytes()), (1, 4, 0));
+  /// a.alloc(0);
+  /// assert_eq!((a.chunk_count(), a.allocated_bytes(), a.used_bytes()), (1, 4, 4));
+  /// a.alloc(1);
+  /// assert_eq!((a.chunk_count(), a.allocated_bytes(), a.used_bytes()), (2, 12, 8));
+  /// // the second chunk is retired with a free slot, and a chunk of 10 elements is allocated
+  /// a.alloc_slice(vec![2; 10]);
+  /// assert_eq!((a.chunk_count(), a.allocated_bytes(), a.used_bytes()), (3, 52, 48));
+  /// a.alloc(3);
+  /// assert_eq!((a.chunk_count(), a.allocated_bytes(), a.used_bytes()), (4, 132, 52));
+  /// ```
+  pub fn allocated_bytes(&self) -> usize {
+    let Inner { cur, rest, .. } = unsafe { &*self.0.get() };
+    (rest.iter().map(Vec::capacity).sum::<usize>() + cur.capacity()) * mem::size_of::<T>()
+  }
+
+  /// Returns the total size in bytes of all allocated elements, i.e. `len() * size_of::<T>()`.
+  ///
+  /// Slots reserved by `alloc_uninit` and `alloc_uninit_slice` are not included.
+  #[inline]
+  pub fn used_bytes(&self) -> usize { self.len() * mem::size_of::<T>() }
+
+  /// Returns the number of chunks, including the current one.
+  #[inline]
+  pub fn chunk_count(&self) -> usize {
+    let Inner { rest, .. } = unsafe { &*self.0.get() };
+    rest.len() + 1
+  }
+
+  /// Drops all elements in the arena, and keeps the largest chunk for future allocations.
+  ///
+  /// This is `reset` without reporting what is retained.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  /// use std::rc::Rc;
+  ///
+  /// let r = Rc::new(());
+  /// let mut a = Arena::new();
+  /// let mut p = Vec::new();
+  /// for _ in 0..100 { p.push(a.alloc(r.clone()) as *mut Rc<()>); }
+  /// a.clear();
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  /// assert_eq!(a.len(), 0);
+  /// // the largest chunk can hold 64 elements, starting from the 64th allocated element
+  /// for i in 0..64 { assert_eq!(a.alloc(r.clone()) as *mut Rc<()>, p[63].wrapping_add(i)); }
+  /// drop(a);
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  /// ```
+  #[inline]
+  pub fn clear(&mut self) { self.reset(); }
+
+  /// Drops all elements in the arena, frees all chunks except the largest one, and returns its capacity.
+  ///
+  /// The retained chunk becomes the current chunk, so if the arena is refilled with no more elements
+  /// than it held before, no allocation happens. Memory used by the arena never exceeds its peak usage
+  /// of the last cycle, so it doesn't grow across cycles either.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let mut a = Arena::new();
+  /// a.alloc_slice(vec![0; 100]); // a chunk of 100 elements
+  /// a.alloc(0); // a chunk of 200 elements
+  /// assert_eq!(a.reset(), 200);
+  /// assert_eq!(a.len(), 0);
+  /// let x = a.alloc_slice(vec![1; 60]).as_mut_ptr();
+  /// assert_eq!(a.alloc_slice(vec![2; 40]).as_mut_ptr(), x.wrapping_add(60));
+  /// // this one doesn't fit in the retained chunk, so a chunk of 400 elements is allocated
+  /// a.alloc_slice(vec![3; 150]);
+  /// assert_eq!(a.reset(), 400);
+  /// ```
+  pub fn reset(&mut self) -> usize {
+    let inner = self.0.get_mut();
+    inner.committed.clear();
+    inner.rest.push(mem::take(&mut inner.cur));
+    let largest = (0..inner.rest.len()).max_by_key(|&i| inner.rest[i].capacity()).unwrap();
+    let mut cur = inner.rest.swap_remove(largest);
+    inner.rest.clear();
+    inner.ends.clear();
+    cur.clear();
+    inner.end = cur.capacity();
+    inner.cur = cur;
+    inner.end
+  }
+
+  /// Moves all elements into a single chunk if there is more than one, and returns them as a slice in allocation order.
+  ///
+  /// The new chunk holds exactly `len()` elements, plus the slices committed by `assume_init_slice`, which are moved too.
+  /// Since elements are moved, all references previously returned by the arena are invalidated, which the borrow
+  /// checker enforces by `&mut self`. If no chunk has been retired, nothing is moved.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let mut a = Arena::new();
+  /// for i in 0..10 { a.alloc(i); }
+  /// a.alloc_slice(vec![10, 11]);
+  /// let s = a.make_contiguous();
+  /// assert!(s.iter().copied().eq(0..12));
+  /// s.reverse();
+  /// let p = s.as_ptr();
+  /// assert_eq!((a.chunk_count(), a.allocated_bytes()), (1, 48));
+  /// assert_eq!(a.make_contiguous().as_ptr(), p);
+  /// a.alloc(12);
+  /// assert_eq!(a.first(), Some(&11));
+  /// ```
+  pub fn make_contiguous(&mut self) -> &mut [T] where T: Unpin {
+    let inner = self.0.get_mut();
+    if !inner.rest.is_empty() {
+      let len = inner.retired_len() + inner.cur.len();
+      let reserved = inner.committed.0.iter().map(|p| p.len()).sum::<usize>();
+      let mut new = Vec::with_capacity(len.checked_add(reserved).expect("capacity overflow"));
+      for c in &mut inner.rest { new.append(c); }
+      new.append(&mut inner.cur);
+      // committed slices are moved to the top of the new chunk, as if they had been reserved there
+      let mut end = new.capacity();
+      for p in &mut inner.committed.0 {
+        end -= p.len();
+        unsafe {
+          let dst = new.as_mut_ptr().add(end);
+          ptr::copy_nonoverlapping(*p as *mut T, dst, p.len());
+          *p = ptr::slice_from_raw_parts_mut(dst, p.len());
+        }
+      }
+      inner.rest.clear();
+      inner.ends.clear();
+      inner.end = end;
+      inner.cur = new;
+    }
+    &mut inner.cur[..]
+  }
+
+  /// Returns all elements as a slice if no chunk has been retired, or `None` otherwise.
+  ///
+  /// This is free, unlike `make_contiguous`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let mut a = Arena::with_capacity(4);
+  /// for i in (0..4).rev() {
+  ///   a.alloc(i);
+  /// }
+  /// a.as_single_slice().unwrap().sort();
+  /// assert_eq!(a.as_single_slice().unwrap(), &[0, 1, 2, 3]);
+  /// a.alloc(4);
+  /// assert_eq!(a.as_single_slice(), None);
+  /// ```
+  #[inline]
+  pub fn as_single_slice(&mut self) -> Option<&mut [T]> where T: Unpin {
+    let inner = self.0.get_mut();
+    if inner.rest.is_empty() { Some(&mut inner.cur[..]) } else { None }
+  }
+
+  /// Moves all elements out of the arena into a `Vec` in allocation order, and keeps the largest chunk, like `reset`.
+  ///
+  /// Unlike `into_vec`, the arena stays alive for reuse. Slices committed by `assume_init_slice` are dropped.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  /// use std::rc::Rc;
+  ///
+  /// let r = Rc::new(());
+  /// let mut a = Arena::<(usize, Rc<()>)>::new();
+  /// for i in 0..10 { a.alloc((i, r.clone())); }
+  /// a.alloc_slice((10..20).map(|i| (i, r.clone())).collect());
+  /// assert_eq!(a.chunk_count(), 5);
+  /// let v = a.take_all();
+  /// assert!(v.iter().map(|d| d.0).eq(0..20));
+  /// assert_eq!((Rc::strong_count(&r), a.len(), a.chunk_count(), a.allocated_bytes()), (21, 0, 1, 16 * std::mem::size_of::<(usize, Rc<()>)>()));
+  /// for i in 0..16 { a.alloc((i, r.clone())); }
+  /// assert_eq!(a.chunk_count(), 1);
+  /// drop(v);
+  /// assert_eq!(Rc::strong_count(&r), 17);
+  /// drop(a);
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  /// ```
+  pub fn take_all(&mut self) -> Vec<T> where T: Unpin {
+    let inner = self.0.get_mut();
+    let mut v = Vec::with_capacity(inner.retired_len() + inner.cur.len());
+    for c in &mut inner.rest { v.append(c); }
+    v.append(&mut inner.cur);
+    self.reset();
+    v
+  }
+
+  /// Releases the unused capacity of the current chunk.
+  ///
+  /// The current chunk is reallocated to fit its elements, so they may be moved, which is fine since this
+  /// method takes `&mut self`. Retired chunks are left alone, including the unused tail of a chunk retired early
+  /// because a slice didn't fit in it. If the current chunk contains slots reserved by `alloc_uninit` or
+  /// `alloc_uninit_slice`, nothing is released. The next allocation will need a new chunk.
+  /// If it holds no element, it is freed, and the next chunk is as large as if it had never been allocated,
+  /// doubling from the last retired chunk.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let mut a = Arena::<u32>::with_capacity(1000);
+  /// a.alloc_slice(vec![1, 2, 3]);
+  /// assert_eq!(a.allocated_bytes(), 4000);
+  /// a.shrink_to_fit();
+  /// assert_eq!(a.allocated_bytes(), 12);
+  /// assert!(a.iter_mut().map(|x| *x).eq(1..4));
+  /// a.alloc(4);
+  /// assert_eq!((a.chunk_count(), a.allocated_bytes()), (2, 36));
+  ///
+  /// let mut a = Arena::<u64>::new();
+  /// for i in 0..7 { a.alloc(i); }
+  /// // the chunks of 1, 2 and 4 elements are full, and the new current chunk of 8 elements is empty
+  /// a.reserve(1);
+  /// a.shrink_to_fit();
+  /// assert_eq!(a.allocated_bytes(), 7 * 8);
+  /// a.alloc(7);
+  /// assert_eq!((a.chunk_count(), a.allocated_bytes()), (4, 15 * 8));
+  /// for i in 8..64 { a.alloc(i); }
+  /// assert_eq!(a.chunk_count(), 7);
+  /// ```
+  pub fn shrink_to_fit(&mut self) where T: Unpin {
+    let inner = self.0.get_mut();
+    if inner.end == inner.cur.capacity() {
+      inner.cur.shrink_to_fit();
+      inner.end = inner.cur.capacity();
+    }
+  }
+
+  /// Records the current state of the arena, so that it can be restored by `truncate`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let mut a = Arena::new();
+  /// a.alloc_slice(vec![0, 1, 2]);
+  /// let cp = a.checkpoint();
+  /// for i in 3..100 { a.alloc(i); }
+  /// a.truncate(cp);
+  /// assert_eq!(a.len(), 3);
+  /// a.alloc(3);
+  /// a.truncate(cp); // a checkpoint can be used again
+  /// assert!(a.into_vec().into_iter().eq(0..3));
+  /// ```
+  #[inline]
+  pub fn checkpoint(&self) -> ArenaCheckpoint {
+    let inner = unsafe { &*self.0.get() };
+    ArenaCheckpoint { id: inner.id, chunk: inner.rest.len(), len: inner.cur.len(), committed: inner.committed.0.len() }
+  }
+
+  /// Drops all elements allocated after `cp` was recorded, and frees all chunks created after it.
+  ///
+  /// The chunk that was current when `cp` was recorded becomes the current chunk again, even if it has been retired,
+  /// so its free slots, including slots reserved by `alloc_uninit` and not committed, are reused.
+  /// Slices committed by `assume_init_slice` after `cp` was recorded are dropped too.
+  ///
+  /// The result is unspecified, but memory safe, if `cp` was recorded while the arena was being
+  /// filled by one of its own methods, e.g. inside the closure passed to `alloc_with`.
+  ///
+  /// ## Panics
+  ///
+  /// Panics if `cp` was recorded by another arena, or if it is newer than the state of this arena,
+  /// e.g. because of an earlier `truncate` to an older checkpoint, or `clear`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  /// use std::rc::Rc;
+  ///
+  /// let r = Rc::new(());
+  /// let mut a = Arena::new();
+  /// a.alloc((0, r.clone()));
+  /// let cp = a.checkpoint();
+  /// // the chunk of `cp` is retired by these
+  /// for i in 1..10 { a.alloc((i, r.clone())); }
+  /// a.truncate(cp);
+  /// assert_eq!((Rc::strong_count(&r), a.len(), a.chunk_count()), (2, 1, 1));
+  ///
+  /// // truncating to the exact boundary of a chunk, since the first chunk is full
+  /// let cp = a.checkpoint();
+  /// a.alloc((1, r.clone()));
+  /// assert_eq!(a.chunk_count(), 2);
+  /// let cp2 = a.checkpoint();
+  /// a.truncate(cp);
+  /// assert_eq!((Rc::strong_count(&r), a.len(), a.chunk_count()), (2, 1, 1));
+  /// assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| a.truncate(cp2))).is_err());
+  /// assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| a.truncate(Arena::<u8>::new().checkpoint()))).is_err());
+  /// assert!(a.iter_mut().map(|d| d.0).eq(0..1));
+  /// ```
+  pub fn truncate(&mut self, cp: ArenaCheckpoint) {
+    let inner = self.0.get_mut();
+    assert_eq!(cp.id, inner.id, "checkpoint recorded by another arena");
+    let n = inner.rest.len();
+    let chunk_len = if cp.chunk < n { inner.rest[cp.chunk].len() } else if cp.chunk == n { inner.cur.len() } else { 0 };
+    assert!(cp.chunk <= n && cp.len <= chunk_len, "checkpoint is newer than the arena");
+    let mut tail = Vec::new();
+    if cp.chunk < n {
+      tail = inner.rest.split_off(cp.chunk + 1);
+      tail.push(mem::replace(&mut inner.cur, inner.rest.pop().unwrap()));
+      inner.ends.truncate(cp.chunk);
+    }
+    // committed slices after `cp`, or in chunks to be freed, are dropped before the chunks holding them are freed
+    let mut doomed = Committed(Vec::new());
+    for (i, p) in mem::take(&mut inner.committed.0).into_iter().enumerate() {
+      if i >= cp.committed || tail.iter().any(|c| contains(c, p)) { doomed.0.push(p); } else { inner.committed.0.push(p); }
+    }
+    let cur = &inner.cur;
+    let size = mem::size_of::<T>().max(1);
+    inner.end = inner.committed.0.iter().filter(|&&p| contains(cur, p))
+      .map(|&p| (p as *mut T as usize - cur.as_ptr() as usize) / size).fold(cur.capacity(), usize::min);
+    inner.cur.truncate(cp.len);
+    drop(doomed);
+    drop(tail);
+  }
+
+  /// Returns an iterator over the elements of each chunk, in allocation order.
+  ///
+  /// Retired chunks come first, followed by the current chunk, so it yields `chunk_count()` slices.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let mut a = Arena::new();
+  /// for i in 0..4 { a.alloc(i); }
+  /// a.alloc_slice(vec![4, 5]);
+  /// assert_eq!(a.chunks().collect::<Vec<_>>(), [&[0][..], &[1, 2], &[3, 4, 5]]);
+  /// assert_eq!(a.chunks().count(), a.chunk_count());
+  /// assert!(a.chunks().flatten().copied().eq(0..6));
+  /// ```
+  pub fn chunks(&mut self) -> impl Iterator<Item = &[T]> {
+    let Inner { cur, rest, .. } = self.0.get_mut();
+    rest.iter().map(|c| &c[..]).chain(iter::once(&cur[..]))
+  }
+
+  /// Returns an iterator over the elements of each chunk as mutable slices, in allocation order.
+  ///
+  /// See `chunks` for details.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let mut a = Arena::new();
+  /// assert_eq!(a.chunks_mut().map(|c| c.len()).sum::<usize>(), 0);
+  /// for i in 0..10 { a.alloc(i); }
+  /// for c in a.chunks_mut() { c.reverse(); }
+  /// assert!(a.iter_mut().map(|x| *x).eq([0, 2, 1, 6, 5, 4, 3, 9, 8, 7]));
+  /// assert_eq!(a.chunks_mut().map(|c| c.len()).sum::<usize>(), a.len());
+  /// ```
+  pub fn chunks_mut(&mut self) -> impl Iterator<Item = &mut [T]> where T: Unpin {
+    let Inner { cur, rest, .. } = self.0.get_mut();
+    rest.iter_mut().map(|c| &mut c[..]).chain(iter::once(&mut cur[..]))
+  }
+
+  /// Moves all chunks of `other` into this arena, without copying or moving any element.
+  ///
+  /// The elements of `other` come after the elements of `self` in `iter_mut`, `chunks`, `into_vec` and `into_iter`,
+  /// and they are dropped exactly once, with this arena. The current chunk of `other` becomes the current chunk
+  /// of `self`, so the free slots of the old current chunk of `self` are no longer used.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let mut a = Arena::new();
+  /// a.alloc_slice(vec![0, 1, 2]);
+  /// let b = Arena::new();
+  /// let p = b.alloc_slice(vec![3, 4]).as_ptr();
+  /// b.alloc(5);
+  /// a.append(b);
+  /// assert_eq!(a.len(), 6);
+  /// assert!(a.chunks().any(|c| c.as_ptr() == p));
+  /// a.alloc(6);
+  /// assert!(a.into_vec().into_iter().eq(0..7));
+  /// ```
+  pub fn append(&mut self, other: Arena<T>) {
+    let Inner { mut committed, cur, rest, end, .. } = other.0.into_inner();
+    let inner = self.0.get_mut();
+    inner.committed.0.append(&mut committed.0);
+    let old_end = mem::replace(&mut inner.end, end);
+    let old = mem::replace(&mut inner.cur, cur);
+    if !is_vacant(&old, old_end) { inner.retire(old); }
+    for c in rest { inner.retire(c); }
+  }
+
+  /// Consumes the arena, and returns a `Vec` containing all elements in allocation order.
+  ///
+  /// Elements are moved, not cloned. If no chunk has been retired, the current chunk is returned as is.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  /// use std::rc::Rc;
+  ///
+  /// let r = Rc::new(());
+  /// let a = Arena::new();
+  /// for i in 0..5 { a.alloc((i, r.clone())); }
+  /// a.alloc_slice((5..10).map(|i| (i, r.clone())).collect());
+  /// let v = a.into_vec();
+  /// assert_eq!(Rc::strong_count(&r), 11);
+  /// assert!(v.iter().map(|d| d.0).eq(0..10));
+  /// drop(v);
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  /// ```
+  pub fn into_vec(self) -> Vec<T> where T: Unpin {
+    let Inner { mut cur, rest, committed, .. } = self.0.into_inner();
+    drop(committed);
+    if rest.is_empty() { return cur; }
+    let mut v = Vec::with_capacity(rest.iter().map(Vec::len).sum::<usize>() + cur.len());
+    for mut chunk in rest { v.append(&mut chunk); }
+    v.append(&mut cur);
+    v
+  }
+
+  /// Creates an empty arena, and calls `f` with it as a `BrandedArena`, whose indices can only be used within `f`.
+  ///
+  /// The arena and its objects are dropped when `f` returns.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let s = Arena::scope(|a| {
+  ///   let i = a.alloc(String::from("scoped"));
+  ///   a[i].push('!');
+  ///   a[i].clone()
+  /// });
+  /// assert_eq!(s, "scoped!");
+  /// ```
+  pub fn scope<R>(f: impl for<'id> FnOnce(&mut BrandedArena<'id, T>) -> R) -> R {
+    f(&mut BrandedArena(Arena::new(), PhantomData))
+  }
+}
+
+impl<T> Inner<T> {
+  /// Returns the number of free slots in `cur`.
+  #[inline]
+  fn spare(&self) -> usize { self.end - self.cur.len() }
+
+  /// Counts `n` free slots of `cur` as allocated elements, growing if needed, and returns a pointer to the first one.
+  ///
+  /// The caller must initialize them before anything else can access the arena.
+  #[inline]
+  unsafe fn claim(&mut self, n: usize) -> *mut T {
+    match self.try_claim(n) { Ok(p) => p, Err(e) => e.raise() }
+  }
+
+  /// Fallible version of `claim`.
+  #[inline]
+  unsafe fn try_claim(&mut self, n: usize) -> Result<*mut T, AllocError> {
+    if self.spare() < n {
+      self.try_grow(n)?;
+    }
+    let len = self.cur.len();
+    self.cur.set_len(len + n);
+    Ok(self.cur.as_mut_ptr().add(len))
+  }
+
+  /// Retire `cur` and replace it with a chunk that can hold at least `additional` elements.
+  ///
+  /// The new capacity is twice the old one, so growth starts from the initial capacity. The end of `cur` is settled
+  /// first, which may leave enough room, so that nothing else is done.
+  #[cold]
+  fn grow(&mut self, additional: usize) {
+    if let Err(e) = self.try_grow(additional) { e.raise() }
+  }
+
+  /// Fallible version of `grow`, which leaves the arena untouched on failure.
+  #[cold]
+  fn try_grow(&mut self, additional: usize) -> Result<(), AllocError> {
+    self.settle();
+    if self.spare() >= additional { return Ok(()); }
+    let (cap, layout) = self.next_chunk(additional)?;
+    let mut new = Vec::new();
+    new.try_reserve_exact(cap).map_err(|_| AllocError::alloc((), layout))?;
+    self.try_reserve_retired(1)?;
+    self.replace_cur(new);
+    Ok(())
+  }
+
+  /// Returns the capacity and layout of the chunk `grow` would allocate.
+  #[inline]
+  fn next_chunk(&self, additional: usize) -> Result<(usize, Layout), AllocError> {
+    // a current chunk freed by `shrink_to_fit` has no capacity, so keep doubling from the last retired chunk
+    let base = if self.cur.capacity() == 0 { self.rest.last().map_or(0, Vec::capacity) } else { self.cur.capacity() };
+    let cap = base.checked_mul(2).ok_or(AllocError::capacity_overflow(()))?.max(additional).max(1);
+    Ok((cap, Layout::array::<T>(cap).map_err(|_| AllocError::capacity_overflow(()))?))
+  }
+
+  /// Replaces `cur` with `new`, retiring it unless it is vacant. `rest` must have room for one more chunk.
+  #[inline]
+  fn replace_cur(&mut self, new: Vec<T>) {
+    let old_end = mem::replace(&mut self.end, new.capacity());
+    let old = mem::replace(&mut self.cur, new);
+    if !is_vacant(&old, old_end) { self.retire(old); }
+  }
+
+  /// Makes room for an adopted chunk to be pushed to `rest`, retiring `cur` unless it is vacant.
+  ///
+  /// Leaves the arena untouched on failure.
+  #[cold]
+  fn try_prepare_adopt(&mut self) -> Result<(), AllocError> {
+    self.settle();
+    self.try_reserve_retired(2)?;
+    if !is_vacant(&self.cur, self.end) {
+      let cap = self.cur.capacity();
+      let mut new = Vec::new();
+      new.try_reserve_exact(cap).map_err(|_| AllocError::alloc((), Layout::array::<T>(cap).unwrap()))?;
+      self.end = new.capacity();
+      let old = mem::replace(&mut self.cur, new);
+      self.retire(old);
+    }
+    Ok(())
+  }
+
+  /// Returns a pointer to the `i`-th element, see `Arena::get`.
+  fn get(&mut self, i: usize) -> Option<*mut T> {
+    let retired = self.retired_len();
+    let (c, j) = if i >= retired { (&mut self.cur, i - retired) } else {
+      let k = self.ends.partition_point(|&e| e <= i);
+      (&mut self.rest[k], i - if k == 0 { 0 } else { self.ends[k - 1] })
+    };
+    // no reference to the element is created, since other elements may be borrowed
+    if j < c.len() { Some(unsafe { c.as_mut_ptr().add(j) }) } else { None }
+  }
+
+  /// Pushes `chunk` to `rest`, whose length must not change, unless `ends` is updated too.
+  #[inline]
+  fn retire(&mut self, chunk: Vec<T>) {
+    self.ends.push(self.retired_len() + chunk.len());
+    self.rest.push(chunk);
+  }
+
+  /// Makes sure that `n` chunks can be retired without allocating.
+  fn try_reserve_retired(&mut self, n: usize) -> Result<(), AllocError> {
+    self.rest.try_reserve(n).map_err(|_| AllocError::alloc((), Layout::array::<Vec<T>>(n).unwrap()))?;
+    self.ends.try_reserve(n).map_err(|_| AllocError::alloc((), Layout::array::<usize>(n).unwrap()))
+  }
+}
+
+impl<T> Chunks for Inner<T> {
+  type Item = T;
+
+  #[inline]
+  fn tails(&mut self) -> &mut Tails<T> { &mut self.tails }
+
+  #[inline]
+  fn len(&self) -> usize { self.cur.len() }
+
+  #[inline]
+  unsafe fn set_len(&mut self, len: usize) { self.cur.set_len(len) }
+
+  #[inline]
+  fn end(&mut self) -> &mut usize { &mut self.end }
+
+  #[inline]
+  fn as_mut_ptr(&mut self) -> *mut T { self.cur.as_mut_ptr() }
+
+  #[inline]
+  fn retired_len(&self) -> usize { self.ends.last().copied().unwrap_or(0) }
+
+  #[inline]
+  fn ends(&mut self) -> &mut [usize] { &mut self.ends }
+
+  #[inline]
+  fn reserve(&mut self, additional: usize) {
+    if self.spare() < additional {
+      self.grow(additional);
+    }
+  }
+
+  /// The new chunk is allocated as if the elements were allocated in one go.
+  #[cold]
+  fn grow_tail(&mut self) {
+    let mut tail = self.tails.tail.unwrap();
+    let (cap, layout) = match self.next_chunk(tail.pending + 1) { Ok(x) => x, Err(e) => e.raise() };
+    let mut new = Vec::new();
+    if new.try_reserve_exact(cap).is_err() { AllocError::alloc((), layout).raise(); }
+    if let Err(e) = self.try_reserve_retired(1) { e.raise(); }
+    unsafe { ptr::copy_nonoverlapping(self.cur.as_ptr().add(self.cur.len()), new.as_mut_ptr(), tail.pending); }
+    self.end = tail.end;
+    self.replace_cur(new);
+    tail.end = mem::replace(&mut self.end, 0);
+    self.tails.tail = Some(tail);
+  }
+
+  #[inline]
+  unsafe fn forget_retired(&mut self, k: usize, len: usize) {
+    self.rest[k].set_len(len);
+    for c in &mut self.rest[k + 1..] { c.set_len(0); }
+  }
+}
+
+/// The slot of a value being constructed by `Arena::alloc_cyclic`.
+pub struct ArenaSlot<T>(NonNull<T>);
+
+impl<T> ArenaSlot<T> {
+  /// Returns the address the value will be written to.
+  #[inline]
+  pub fn as_ptr(&self) -> NonNull<T> { self.0 }
+}
+
+/// A view of an `Arena` which can be indexed by position, created by `Arena::indexed`.
+pub struct Indexed<'a, T>(&'a mut Arena<T>);
+
+impl<T> Indexed<'_, T> {
+  /// Returns the number of elements allocated in the arena.
+  #[inline]
+  pub fn len(&self) -> usize { self.0.len() }
+
+  /// Returns `true` if no element has been allocated in the arena.
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.0.is_empty() }
+
+  /// Returns a pointer to the `i`-th element, or panics if `i >= len()`.
+  #[inline]
+  fn ptr(&self, i: usize) -> *mut T {
+    // the arena is borrowed mutably by `self`, and `Inner::get` creates no reference to any element
+    match unsafe { (*self.0.0.get()).get(i) } {
+      Some(p) => p,
+      None => panic!("index {} out of range for arena of length {}", i, self.len()),
+    }
+  }
+}
+
+impl<T> Index<usize> for Indexed<'_, T> {
+  type Output = T;
+  #[inline]
+  fn index(&self, i: usize) -> &T { unsafe { &*self.ptr(i) } }
+}
+
+impl<T: Unpin> IndexMut<usize> for Indexed<'_, T> {
+  #[inline]
+  fn index_mut(&mut self, i: usize) -> &mut T { unsafe { &mut *self.ptr(i) } }
+}
+
+/// A state of an `Arena` recorded by `Arena::checkpoint`, which can be restored by `Arena::truncate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArenaCheckpoint {
+  id: usize,
+  chunk: usize,
+  len: usize,
+  committed: usize,
+}
+
+/// Returns a new id for an arena, which is never 0.
+pub(crate) fn next_id() -> usize {
+  static NEXT: AtomicUsize = AtomicUsize::new(1);
+  NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Returns `true` if the slice `p` starts within the buffer of `chunk`.
+#[inline]
+fn contains<T>(chunk: &Vec<T>, p: *mut [T]) -> bool {
+  let start = chunk.as_ptr() as usize;
+  (start..start + chunk.capacity() * mem::size_of::<T>()).contains(&(p as *mut T as usize))
+}
+
+/// The minimum size in bytes of a slice whose buffer is adopted by `Arena::alloc_slice`.
+const ADOPT_BYTES: usize = 1 << 16;
+
+/// Slices committed by `Arena::assume_init_slice`, which are dropped in place when this is dropped.
+struct Committed<T>(Vec<*mut [T]>);
+
+unsafe impl<T: Send> Send for Committed<T> {}
+
+impl<T> Committed<T> {
+  fn clear(&mut self) {
+    for p in self.0.drain(..) { unsafe { ptr::drop_in_place(p); } }
+  }
+}
+
+unsafe impl<#[may_dangle] T> Drop for Committed<T> {
+  fn drop(&mut self) {
+    for &p in &self.0 { unsafe { ptr::drop_in_place(p); } }
+  }
+}
+
+/// Returns `true` if `chunk` holds neither elements nor slots reserved by `alloc_uninit`, given the `end` of its free slots.
+///
+/// Such a chunk can be freed without invalidating any reference.
+#[inline]
+fn is_vacant<T>(chunk: &Vec<T>, end: usize) -> bool { chunk.is_empty() && end == chunk.capacity() }
+
+impl<T: Clone> Arena<T> {
+  /// Clones a slice into the arena, and returns a mutable reference to the clone.
+  ///
+  /// If a `clone` panics, the elements cloned so far are owned by the arena and dropped with it.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  /// use std::{panic::{self, AssertUnwindSafe}, rc::Rc};
+  ///
+  /// let a = Arena::new();
+  /// let src = [String::from("a"), String::from("b")];
+  /// assert_eq!(a.alloc_slice_clone(&src), &src);
+  ///
+  /// struct P(usize, Rc<()>);
+  /// impl Clone for P {
+  ///   fn clone(&self) -> Self { if self.0 == 3 { panic!() } P(self.0, self.1.clone()) }
+  /// }
+  ///
+  /// let r = Rc::new(());
+  /// let src = (0..5).map(|i| P(i, r.clone())).collect::<Vec<_>>();
+  /// let a = Arena::new();
+  /// assert!(panic::catch_unwind(AssertUnwindSafe(|| a.alloc_slice_clone(&src))).is_err());
+  /// assert_eq!((Rc::strong_count(&r), a.len()), (9, 3));
+  /// drop(a);
+  /// assert_eq!(Rc::strong_count(&r), 6);
+  /// ```
+  pub fn alloc_slice_clone(&self, src: &[T]) -> &mut [T] {
+    let mut r = Reserve::new(&self.0, src.len());
+    for t in src { r.push(t.clone()); }
+    r.finish()
+  }
+
+  /// Allocates `n` copies of `value` contiguously in the arena, and returns a mutable reference to them.
+  ///
+  /// `value` is cloned `n - 1` times and moved into the last slot. If `n` is 0, `value` is dropped.
+  /// If a `clone` panics, `value` is dropped, and the clones made so far are owned by the arena and dropped with it.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  /// use std::{cell::Cell, panic::{self, AssertUnwindSafe}, rc::Rc};
+  ///
+  /// let a = Arena::new();
+  /// assert_eq!(a.alloc_many(3, String::from("a")), ["a", "a", "a"]);
+  ///
+  /// struct P<'a>(&'a Cell<usize>, Rc<()>);
+  /// impl Clone for P<'_> {
+  ///   fn clone(&self) -> Self {
+  ///     self.0.set(self.0.get() + 1);
+  ///     if self.0.get() == 3 { panic!() }
+  ///     P(self.0, self.1.clone())
+  ///   }
+  /// }
+  ///
+  /// let (clones, r) = (Cell::new(0), Rc::new(()));
+  /// let a = Arena::new();
+  /// assert_eq!(a.alloc_many(1, P(&clones, r.clone())).len(), 1);
+  /// assert_eq!(a.alloc_many(0, P(&clones, r.clone())).len(), 0);
+  /// assert_eq!((clones.get(), Rc::strong_count(&r)), (0, 2));
+  /// let res = panic::catch_unwind(AssertUnwindSafe(|| { a.alloc_many(5, P(&clones, r.clone())); }));
+  /// assert!(res.is_err());
+  /// assert_eq!((clones.get(), Rc::strong_count(&r), a.len()), (3, 4, 3));
+  /// drop(a);
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  /// ```
+  pub fn alloc_many(&self, n: usize, value: T) -> &mut [T] {
+    if n == 0 { return &mut []; }
+    let mut r = Reserve::new(&self.0, n);
+    for _ in 1..n { r.push(value.clone()); }
+    r.push(value);
+    r.finish()
+  }
+}
+
+impl<T: Default> Arena<T> {
+  /// Allocates a slice of `n` default values in the arena, and returns a mutable reference to it.
+  ///
+  /// ## Example
+  ///
+  /// Allocate the distance table of a breadth-first search:
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let edges = [vec![1, 2], vec![3], vec![3], vec![]];
+  /// let a = Arena::new();
+  /// let dist: &mut [Option<u32>] = a.alloc_slice_default(edges.len());
+  /// let mut queue = std::collections::VecDeque::from(vec![0]);
+  /// dist[0] = Some(0);
+  /// while let Some(u) = queue.pop_front() {
+  ///   for &v in &edges[u] {
+  ///     if dist[v].is_none() {
+  ///       dist[v] = Some(dist[u].unwrap() + 1);
+  ///       queue.push_back(v);
+  ///     }
+  ///   }
+  /// }
+  /// assert_eq!(dist, &[Some(0), Some(1), Some(1), Some(2)]);
+  /// assert_eq!(a.alloc_slice_default(0), &[]);
+  /// ```
+  #[inline]
+  pub fn alloc_slice_default(&self, n: usize) -> &mut [T] {
+    self.alloc_slice_fill_with(n, |_| T::default())
+  }
+}
+
+impl<T: Copy> Arena<T> {
+  /// Copies a slice into the arena, and returns a mutable reference to the copy.
+  ///
+  /// Unlike `alloc_slice(src.to_vec())`, this doesn't allocate a temporary `Vec`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let a = Arena::new();
+  /// let x = a.alloc_slice_copy(&[1, 2, 3]);
+  /// x[0] = 0;
+  /// assert_eq!(x, &[0, 2, 3]);
+  /// assert_eq!(a.alloc_slice_copy(&[]), &[]);
+  /// assert_eq!(a.len(), 3);
+  /// ```
+  #[inline]
+  pub fn alloc_slice_copy(&self, src: &[T]) -> &mut [T] {
+    unsafe {
+      let last = (*self.0.get()).claim(src.len());
+      last.copy_from_nonoverlapping(src.as_ptr(), src.len());
+      slice::from_raw_parts_mut(last, src.len())
+    }
+  }
+
+  /// Allocates a slice of `n` copies of `value` in the arena, and returns a mutable reference to it.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let a = Arena::new();
+  /// assert_eq!(a.alloc_slice_fill_copy(3, 0u8), &[0, 0, 0]);
+  /// assert_eq!(a.alloc_slice_fill_copy(0, 1u8), &[]);
+  /// assert_eq!(a.len(), 3);
+  /// ```
+  ///
+  /// # Panics
+  ///
+  /// Panic if the new chunk would exceed `isize::MAX` bytes:
+  ///
+  /// ```should_panic
+  /// use arena::Arena;
+  ///
+  /// Arena::new().alloc_slice_fill_copy(usize::MAX / 2, 0u16);
+  /// ```
+  #[inline]
+  pub fn alloc_slice_fill_copy(&self, n: usize, value: T) -> &mut [T] {
+    unsafe {
+      let last = (*self.0.get()).claim(n);
+      for i in 0..n { last.add(i).write(value); }
+      slice::from_raw_parts_mut(last, n)
+    }
+  }
+}
+
+#[cfg(feature = "bytemuck")]
+impl<T: bytemuck::Zeroable> Arena<T> {
+  /// Allocates a slice of `n` zeroed elements in the arena, and returns a mutable reference to it.
+  ///
+  /// If a new chunk is needed, it is allocated by `alloc_zeroed`, so that the allocator can provide memory
+  /// that is already zeroed, e.g. fresh pages from the kernel. Otherwise the slots are zeroed by `write_bytes`.
+  ///
+  /// This requires the `bytemuck` feature.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  ///
+  /// let mut a = Arena::<u32>::new();
+  /// let bitmap = a.alloc_zeroed_slice(1 << 16);
+  /// assert!(bitmap.iter().all(|&x| x == 0));
+  /// a.clear();
+  /// a.alloc_slice_fill_copy(100, 1);
+  /// a.clear();
+  /// assert_eq!(a.alloc_zeroed_slice(100), &[0; 100]);
+  /// assert_eq!(a.chunk_count(), 1);
+  /// ```
+  pub fn alloc_zeroed_slice(&self, n: usize) -> &mut [T] {
+    unsafe {
+      let inner = &mut *self.0.get();
+      inner.settle();
+      if inner.spare() < n && mem::size_of::<T>() != 0 {
+        let (cap, layout) = match inner.next_chunk(n) { Ok(x) => x, Err(e) => e.raise() };
+        let p = alloc_zeroed(layout) as *mut T;
+        if p.is_null() { AllocError::alloc((), layout).raise(); }
+        let new = Vec::from_raw_parts(p, 0, cap);
+        if let Err(e) = inner.try_reserve_retired(1) { e.raise(); }
+        inner.replace_cur(new);
+        // the new chunk is already zeroed
+        return slice::from_raw_parts_mut(inner.claim(n), n);
+      }
+      let p = inner.claim(n);
+      p.write_bytes(0, n);
+      slice::from_raw_parts_mut(p, n)
+    }
+  }
+}
+
+impl<T> Arena<RcSlot<T>> {
+  /// Allocates a value in the arena, and returns an `ArenaRc` pointing to it, with a reference count of 1.
+  ///
+  /// The value is stored in a slot reserved by `alloc_uninit`, so the arena never drops it, see `ArenaRc`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::{Arena, ArenaRc};
+  ///
+  /// let a = Arena::new();
+  /// let x = a.alloc_rc(String::from("shared"));
+  /// let y = x.clone();
+  /// assert!(ArenaRc::ptr_eq(&x, &y));
+  /// assert_eq!((y.as_str(), ArenaRc::strong_count(&x), a.len()), ("shared", 2, 0));
+  /// ```
+  #[inline]
+  pub fn alloc_rc(&self, t: T) -> ArenaRc<'_, T> { ArenaRc::new(self.alloc_uninit(), t) }
+}
+
+impl<T> Default for Arena<T> {
+  /// Equivalent to calling `Arena::<T>::new()`.
+  fn default() -> Self { Self::new() }
+}
+
+impl<T> From<Vec<T>> for Arena<T> {
+  #[inline]
+  fn from(v: Vec<T>) -> Self { Self::from_vec(v) }
+}
+
+impl<T: Unpin> IntoIterator for Arena<T> {
+  type Item = T;
+  type IntoIter = IntoIter<T>;
+
+  /// Consumes the arena, and returns an iterator yielding all elements by value in allocation order.
+  ///
+  /// Elements not yielded are dropped when the iterator is dropped.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Arena;
+  /// use std::rc::Rc;
+  ///
+  /// let r = Rc::new(());
+  /// let new_arena = || {
+  ///   let a = Arena::new();
+  ///   for i in 0..5 { a.alloc((i, r.clone())); }
+  ///   a.alloc_slice((5..10).map(|i| (i, r.clone())).collect());
+  ///   a
+  /// };
+  ///
+  /// let mut it = new_arena().into_iter();
+  /// assert_eq!(it.len(), 10);
+  /// assert!(it.by_ref().map(|d| d.0).eq(0..10));
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  ///
+  /// let mut it = new_arena().into_iter();
+  /// assert!(it.by_ref().take(3).map(|d| d.0).eq(0..3));
+  /// assert_eq!(it.size_hint(), (7, Some(7)));
+  /// assert_eq!(Rc::strong_count(&r), 8);
+  /// drop(it);
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  ///
+  /// drop(new_arena().into_iter());
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  /// ```
+  fn into_iter(self) -> IntoIter<T> {
+    let len = self.len();
+    let Inner { cur, mut rest, committed, .. } = self.0.into_inner();
+    drop(committed);
+    rest.push(cur);
+    IntoIter { chunks: rest.into_iter(), front: Vec::new().into_iter(), back: Vec::new().into_iter(), len }
+  }
+}
+
+/// An iterator that moves elements out of an `Arena`, created by `Arena::into_iter`.
+pub struct IntoIter<T> {
+  chunks: vec::IntoIter<Vec<T>>,
+  front: vec::IntoIter<T>,
+  back: vec::IntoIter<T>,
+  len: usize,
+}
+
+impl<T> Iterator for IntoIter<T> {
+  type Item = T;
+
+  #[inline]
+  fn next(&mut self) -> Option<T> {
+    loop {
+      if let Some(x) = self.front.next() {
+        self.len -= 1;
+        return Some(x);
+      }
+      match self.chunks.next() {
+        Some(chunk) => self.front = chunk.into_iter(),
+        None => {
+          let x = self.back.next()?;
+          self.len -= 1;
+          return Some(x);
+        }
+      }
+    }
+  }
+
+  #[inline]
+  fn size_hint(&self) -> (usize, Option<usize>) { (self.len, Some(self.len)) }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+  #[inline]
+  fn next_back(&mut self) -> Option<T> {
+    loop {
+      if let Some(x) = self.back.next_back() {
+        self.len -= 1;
+        return Some(x);
+      }
+      match self.chunks.next_back() {
+        Some(chunk) => self.back = chunk.into_iter(),
+        None => {
+          let x = self.front.next_back()?;
+          self.len -= 1;
+          return Some(x);
+        }
+      }
+    }
+  }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+/// An iterator over mutable references to the elements of an `Arena`, created by `Arena::iter_mut`.
+pub struct IterMut<'a, T> {
+  chunks: slice::IterMut<'a, Vec<T>>,
+  front: slice::IterMut<'a, T>,
+  back: slice::IterMut<'a, T>,
+  len: usize,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+  type Item = &'a mut T;
+
+  #[inline]
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      if let Some(x) = self.front.next() {
+        self.len -= 1;
+        return Some(x);
+      }
+      match self.chunks.next() {
+        Some(chunk) => self.front = chunk.iter_mut(),
+        None => {
+          let x = self.back.next()?;
+          self.len -= 1;
+          return Some(x);
+        }
+      }
+    }
+  }
+
+  #[inline]
+  fn size_hint(&self) -> (usize, Option<usize>) { (self.len, Some(self.len)) }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+  #[inline]
+  fn next_back(&mut self) -> Option<Self::Item> {
+    loop {
+      if let Some(x) = self.back.next_back() {
+        self.len -= 1;
+        return Some(x);
+      }
+      match self.chunks.next_back() {
+        Some(chunk) => self.back = chunk.iter_mut(),
+        None => {
+          let x = self.front.next_back()?;
+          self.len -= 1;
+          return Some(x);
+        }
+      }
+    }
+  }
+}
+
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {}
@@ -0,0 +1,71 @@
+use core::{fmt, mem::{ManuallyDrop, MaybeUninit}, ops::{Deref, DerefMut}, ptr};
+
+/// An owning handle to a value in an `Arena`, which drops the value when the handle is dropped.
+///
+/// The value is created by `Arena::alloc_boxed`. Its memory stays in the arena until the arena is dropped,
+/// but its destructor runs as soon as the `ArenaBox` is dropped, so that resources held by it are released early.
+/// The arena itself never drops the value, so it is dropped exactly once, or not at all if the `ArenaBox`
+/// is forgotten, e.g. by `mem::forget` or `ArenaBox::leak`.
+///
+/// ## Example
+///
+/// ```
+/// use arena::{Arena, ArenaBox};
+/// use std::{mem, rc::Rc};
+///
+/// let r = Rc::new(());
+/// let a = Arena::new();
+/// mem::forget(a.alloc_boxed((r.clone(), 0)));
+/// let x = ArenaBox::leak(a.alloc_boxed((r.clone(), 1)));
+/// let y = ArenaBox::into_inner(a.alloc_boxed((r.clone(), 2)));
+/// assert_eq!((x.1, y.1, Rc::strong_count(&r)), (1, 2, 4));
+/// drop(y);
+/// drop(a);
+/// assert_eq!(Rc::strong_count(&r), 3);
+/// # // release the clones leaked above, so that Miri doesn't report them
+/// # for _ in 0..2 { unsafe { Rc::decrement_strong_count(Rc::as_ptr(&r)); } }
+/// ```
+pub struct ArenaBox<'a, T>(&'a mut ManuallyDrop<T>);
+
+impl<'a, T> ArenaBox<'a, T> {
+  #[inline]
+  pub(crate) fn new(slot: &'a mut MaybeUninit<T>, t: T) -> Self {
+    slot.write(t);
+    ArenaBox(unsafe { &mut *(slot as *mut MaybeUninit<T> as *mut ManuallyDrop<T>) })
+  }
+
+  /// Moves the value out of the arena, leaving its memory unused.
+  #[inline]
+  pub fn into_inner(b: Self) -> T {
+    let mut b = ManuallyDrop::new(b);
+    unsafe { ManuallyDrop::take(&mut *b.0) }
+  }
+
+  /// Consumes the `ArenaBox` without dropping the value, and returns a mutable reference to it,
+  /// which lives as long as the arena.
+  #[inline]
+  pub fn leak(b: Self) -> &'a mut T {
+    let b = ManuallyDrop::new(b);
+    let r: &'a mut ManuallyDrop<T> = unsafe { ptr::read(&b.0) };
+    r
+  }
+}
+
+impl<T> Deref for ArenaBox<'_, T> {
+  type Target = T;
+  #[inline]
+  fn deref(&self) -> &T { self.0 }
+}
+
+impl<T> DerefMut for ArenaBox<'_, T> {
+  #[inline]
+  fn deref_mut(&mut self) -> &mut T { self.0 }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArenaBox<'_, T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { fmt::Debug::fmt(&**self, f) }
+}
+
+impl<T> Drop for ArenaBox<'_, T> {
+  fn drop(&mut self) { unsafe { ManuallyDrop::drop(self.0); } }
+}
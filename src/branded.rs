@@ -0,0 +1,99 @@
+use core::{fmt, marker::PhantomData, ops::{Index, IndexMut}, ptr::NonNull};
+use crate::Arena;
+
+// invariant in `'id`, so that brands of different scopes never unify
+pub(crate) type Brand<'id> = PhantomData<fn(&'id ()) -> &'id ()>;
+
+/// An `Arena` created by `Arena::scope`, whose objects are looked up by `Idx<'id>` without any check.
+///
+/// Every call to `scope` gets a fresh lifetime `'id`, so an `Idx<'id>` is always returned by this arena. No object
+/// is freed until the scope ends, so an index is always valid, and indexing just dereferences the pointer it holds.
+/// Since `'id` is chosen by `scope`, the objects themselves can't hold indices, which are kept aside instead.
+///
+/// ## Example
+///
+/// Registers of a tiny interpreter, looked up by index:
+///
+/// ```
+/// use arena::{Arena, branded::Idx};
+///
+/// let r0 = Arena::scope(|a| {
+///   let regs: Vec<Idx> = (0..4).map(|i| a.alloc(i * 10)).collect();
+///   // `regs[d] += regs[s]`
+///   for &(d, s) in &[(0, 1), (2, 3), (0, 2)] {
+///     let v = a[regs[s]];
+///     a[regs[d]] += v;
+///   }
+///   assert_eq!(a.len(), 4);
+///   a[regs[0]]
+/// });
+/// assert_eq!(r0, 60);
+/// ```
+///
+/// An index can't be used by another scope:
+///
+/// ```compile_fail
+/// use arena::Arena;
+///
+/// Arena::scope(|a| {
+///   let i = a.alloc(1);
+///   Arena::scope(|b| { b.alloc(2); b[i] })
+/// });
+/// ```
+///
+/// Nor can it escape its scope:
+///
+/// ```compile_fail
+/// use arena::Arena;
+///
+/// let i = Arena::scope(|a| a.alloc(1));
+/// ```
+pub struct BrandedArena<'id, T>(pub(crate) Arena<T>, pub(crate) Brand<'id>);
+
+/// The index of an object in a `BrandedArena<'id, T>`, returned by `BrandedArena::alloc`.
+///
+/// It holds the address of the object, so it is the size of a pointer.
+pub struct Idx<'id>(NonNull<u8>, Brand<'id>);
+
+impl<'id, T> BrandedArena<'id, T> {
+  /// Allocates a value in the arena, and returns its index.
+  #[inline]
+  pub fn alloc(&self, t: T) -> Idx<'id> { Idx(self.0.alloc_raw(t).cast(), PhantomData) }
+
+  /// Returns the number of objects allocated in the arena.
+  #[inline]
+  pub fn len(&self) -> usize { self.0.len() }
+
+  /// Returns `true` if no object has been allocated in the arena.
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.0.is_empty() }
+}
+
+impl<'id, T> Index<Idx<'id>> for BrandedArena<'id, T> {
+  type Output = T;
+  #[inline]
+  fn index(&self, i: Idx<'id>) -> &T { unsafe { &*i.0.cast().as_ptr() } }
+}
+
+impl<'id, T> IndexMut<Idx<'id>> for BrandedArena<'id, T> {
+  #[inline]
+  fn index_mut(&mut self, i: Idx<'id>) -> &mut T { unsafe { &mut *i.0.cast().as_ptr() } }
+}
+
+impl Clone for Idx<'_> {
+  #[inline]
+  fn clone(&self) -> Self { *self }
+}
+
+impl Copy for Idx<'_> {}
+
+impl PartialEq for Idx<'_> {
+  #[inline]
+  fn eq(&self, other: &Self) -> bool { self.0 == other.0 }
+}
+
+impl Eq for Idx<'_> {}
+
+impl fmt::Debug for Idx<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { f.debug_tuple("Idx").field(&self.0).finish() }
+}
@@ -0,0 +1,293 @@
+#[cfg(feature = "no_std")]
+use alloc::{alloc::{alloc, dealloc}, vec::Vec};
+#[cfg(not(feature = "no_std"))]
+use std::{alloc::{alloc, dealloc}, vec::Vec};
+use core::{alloc::Layout, cell::{Cell, UnsafeCell}, marker::PhantomData, mem, ptr::{self, NonNull}};
+use crate::{AllocError, dropless::{PAGE, HUGE_PAGE}};
+
+// chunks are aligned at least this much, so that most allocations at the start of a chunk need no padding
+const CHUNK_ALIGN: usize = 16;
+
+/// An arena of objects of any types, which are allocated from shared chunks of bytes.
+///
+/// Each allocation is aligned for its own type. Values allocated by `alloc` and `alloc_slice` are never dropped,
+/// just like `mem::forget`, so they can refer to each other freely. Values allocated by `alloc_droppable` and
+/// `alloc_slice_droppable` are dropped with the arena, in allocation order, so they may only borrow data that outlives
+/// the arena, denoted by `'a`.
+///
+/// ## Example
+///
+/// ```
+/// use arena::Bump;
+///
+/// enum Expr<'b> { Num(i64), Add(&'b Expr<'b>, &'b Expr<'b>), Call(&'b str, &'b [&'b Expr<'b>]) }
+///
+/// fn eval(e: &Expr) -> i64 {
+///   match e {
+///     Expr::Num(x) => *x,
+///     Expr::Add(l, r) => eval(l) + eval(r),
+///     Expr::Call(_, args) => args.iter().map(|e| eval(e)).max().unwrap_or(0),
+///   }
+/// }
+///
+/// let b = Bump::new();
+/// let one = b.alloc(Expr::Num(1));
+/// let sum = b.alloc(Expr::Add(one, b.alloc(Expr::Num(2))));
+/// let name = std::str::from_utf8(b.alloc_slice(b"max".to_vec())).unwrap();
+/// let call = b.alloc(Expr::Call(name, b.alloc_slice(vec![&*one, &*sum])));
+/// assert_eq!(eval(call), 3);
+/// ```
+pub struct Bump<'a>(pub(crate) UnsafeCell<Inner>, PhantomData<Cell<&'a ()>>);
+
+pub(crate) struct Inner {
+  // the free bytes of the current chunk
+  ptr: *mut u8,
+  end: *mut u8,
+  chunks: Vec<(NonNull<u8>, Layout)>,
+  drops: Vec<Dropper>,
+  // bytes of buffers abandoned by `ArenaVec`
+  wasted: usize,
+}
+
+// a value or slice allocated by `alloc_droppable` or `alloc_slice_droppable`
+struct Dropper {
+  ptr: *mut u8,
+  len: usize,
+  drop: unsafe fn(*mut u8, usize),
+}
+
+unsafe fn drop_slice<T>(p: *mut u8, len: usize) { ptr::drop_in_place(ptr::slice_from_raw_parts_mut(p as *mut T, len)) }
+
+impl<'a> Bump<'a> {
+  /// Creates an empty arena, which doesn't allocate until the first allocation.
+  #[inline]
+  pub const fn new() -> Self {
+    Bump(UnsafeCell::new(Inner { ptr: ptr::null_mut(), end: ptr::null_mut(), chunks: Vec::new(), drops: Vec::new(), wasted: 0 }), PhantomData)
+  }
+
+  /// Creates an arena whose first chunk holds at least `bytes` bytes.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Bump;
+  ///
+  /// let b = Bump::with_capacity(10000);
+  /// let x = b.alloc(0u8) as *mut u8;
+  /// assert_eq!(b.alloc_slice(vec![1u8; 9999]).as_mut_ptr(), x.wrapping_add(1));
+  /// assert_eq!(b.allocated_bytes(), 10000);
+  /// ```
+  pub fn with_capacity(bytes: usize) -> Self {
+    let b = Self::new();
+    if bytes != 0 { unsafe { (*b.0.get()).grow(Layout::new::<()>(), bytes); } }
+    b
+  }
+
+  /// Allocates a value in the arena, and returns a mutable reference to it.
+  ///
+  /// The value is never dropped, see `alloc_droppable` for values whose destructor must run.
+  ///
+  /// ## Example
+  ///
+  /// Values of different alignments share chunks:
+  ///
+  /// ```
+  /// use arena::Bump;
+  ///
+  /// #[repr(align(32))]
+  /// struct Wide([u8; 32]);
+  ///
+  /// let b = Bump::new();
+  /// for i in 0..1000 {
+  ///   let x = b.alloc(i as u8);
+  ///   let y = b.alloc(i as u64);
+  ///   let z = b.alloc(Wide([i as u8; 32]));
+  ///   assert_eq!((y as *mut u64 as usize % 8, z as *mut Wide as usize % 32), (0, 0));
+  ///   assert_eq!((*x, *y, z.0[31]), (i as u8, i, i as u8));
+  /// }
+  /// ```
+  #[inline]
+  pub fn alloc<T>(&self, t: T) -> &mut T { unsafe { &mut *self.alloc_ptr(t) } }
+
+  /// Moves all elements of `v` into the arena as a contiguous slice, and returns a mutable reference to it.
+  ///
+  /// The buffer of `v` is freed, and the elements are never dropped, like `alloc`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Bump;
+  ///
+  /// let b = Bump::new();
+  /// let x = b.alloc_slice(vec![1u8, 2, 3]);
+  /// let y = b.alloc_slice(vec![4u64, 5]);
+  /// let z = b.alloc_slice(Vec::<u32>::new());
+  /// assert_eq!((&*x, &*y, &*z), (&[1, 2, 3][..], &[4, 5][..], &[][..]));
+  /// assert_eq!(y.as_ptr() as usize % 8, 0);
+  /// ```
+  #[inline]
+  pub fn alloc_slice<T>(&self, v: Vec<T>) -> &mut [T] { unsafe { &mut *self.alloc_slice_ptr(v) } }
+
+  /// Allocates a value in the arena, and returns a mutable reference to it. The value is dropped when the arena is dropped.
+  ///
+  /// The value is recorded in a list of pending destructors, unless it doesn't need to be dropped at all.
+  /// It may only borrow data that outlives the arena, so it can't refer to other values in the arena:
+  ///
+  /// ```compile_fail
+  /// use arena::Bump;
+  ///
+  /// let b = Bump::new();
+  /// let s = b.alloc(String::from("leaked"));
+  /// b.alloc_droppable(vec![&*s]);
+  /// ```
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Bump;
+  /// use std::rc::Rc;
+  ///
+  /// let r = Rc::new(());
+  /// let b = Bump::new();
+  /// b.alloc_droppable((r.clone(), 0));
+  /// b.alloc((r.clone(), 1)); // never dropped
+  /// b.alloc_slice_droppable(vec![(r.clone(), 2), (r.clone(), 3)]);
+  /// b.alloc_droppable(String::from("freed"));
+  /// b.alloc_droppable(1u64);
+  /// assert_eq!(Rc::strong_count(&r), 5);
+  /// drop(b);
+  /// assert_eq!(Rc::strong_count(&r), 2);
+  /// # // release the clone allocated by `alloc`, so that Miri doesn't report it as leaked
+  /// # unsafe { Rc::decrement_strong_count(Rc::as_ptr(&r)); }
+  /// ```
+  #[inline]
+  pub fn alloc_droppable<T: 'a>(&self, t: T) -> &mut T {
+    unsafe {
+      let p = self.alloc_ptr(t);
+      self.defer_drop(p, 1);
+      &mut *p
+    }
+  }
+
+  /// Moves all elements of `v` into the arena as a contiguous slice, and returns a mutable reference to it.
+  /// The elements are dropped when the arena is dropped.
+  ///
+  /// See `alloc_droppable` for details.
+  #[inline]
+  pub fn alloc_slice_droppable<T: 'a>(&self, v: Vec<T>) -> &mut [T] {
+    unsafe {
+      let p = self.alloc_slice_ptr(v);
+      self.defer_drop(p as *mut T, p.len());
+      &mut *p
+    }
+  }
+
+  /// Returns the total size in bytes of all chunks.
+  pub fn allocated_bytes(&self) -> usize {
+    let Inner { chunks, .. } = unsafe { &*self.0.get() };
+    chunks.iter().map(|c| c.1.size()).sum()
+  }
+
+  /// Returns the total size in bytes of the buffers abandoned by `ArenaVec` and `ArenaString`, which will never be used.
+  ///
+  /// A buffer is abandoned when it is moved to a larger one, or when its spare capacity is left behind by `into_slice`
+  /// or `into_str`. This doesn't happen if it is the most recent allocation in the arena, so that it can grow or shrink
+  /// in place.
+  pub fn wasted_bytes(&self) -> usize {
+    let Inner { wasted, .. } = unsafe { &*self.0.get() };
+    *wasted
+  }
+
+  #[inline]
+  unsafe fn alloc_ptr<T>(&self, t: T) -> *mut T {
+    let p = self.alloc_layout(Layout::new::<T>()) as *mut T;
+    p.write(t);
+    p
+  }
+
+  #[inline]
+  unsafe fn alloc_slice_ptr<T>(&self, v: Vec<T>) -> *mut [T] {
+    let p = self.alloc_layout(Layout::for_value(&*v)) as *mut T;
+    let (ptr, len, cap) = v.into_raw_parts();
+    p.copy_from_nonoverlapping(ptr, len);
+    let _ = Vec::from_raw_parts(ptr, 0, cap); // deallocate Vec memory without calling element destructor
+    ptr::slice_from_raw_parts_mut(p, len)
+  }
+
+  /// Records `len` values at `p` to be dropped with the arena, if they need to be dropped.
+  #[inline]
+  unsafe fn defer_drop<T>(&self, p: *mut T, len: usize) {
+    if mem::needs_drop::<T>() {
+      (*self.0.get()).drops.push(Dropper { ptr: p as *mut u8, len, drop: drop_slice::<T> });
+    }
+  }
+
+  #[inline]
+  unsafe fn alloc_layout(&self, layout: Layout) -> *mut u8 {
+    let inner = &mut *self.0.get();
+    inner.alloc_layout(layout)
+  }
+}
+
+impl Inner {
+  /// Reserves memory for `layout`, and returns a pointer to it.
+  #[inline]
+  pub(crate) unsafe fn alloc_layout(&mut self, layout: Layout) -> *mut u8 {
+    if layout.size() == 0 { return ptr::without_provenance_mut(layout.align()); }
+    let pad = (self.ptr as usize).wrapping_neg() & (layout.align() - 1);
+    if self.end as usize - (self.ptr as usize) < pad + layout.size() {
+      return self.grow(layout, 0);
+    }
+    let p = self.ptr.add(pad);
+    self.ptr = p.add(layout.size());
+    p
+  }
+
+  /// Resizes the memory of `old` bytes at `p` to `new` bytes, and returns `true`, if it is the most recent allocation
+  /// and fits in the current chunk. Otherwise returns `false`, and nothing changes.
+  #[inline]
+  pub(crate) unsafe fn resize_last(&mut self, p: *mut u8, old: usize, new: usize) -> bool {
+    if old == 0 || p.add(old) != self.ptr || (self.end as usize - p as usize) < new { return false; }
+    self.ptr = p.add(new);
+    true
+  }
+
+  /// Records `bytes` bytes as never to be used again.
+  #[inline]
+  pub(crate) fn waste(&mut self, bytes: usize) { self.wasted += bytes; }
+
+  /// Allocates a new current chunk of at least `min` bytes, reserves memory for `layout` at its start,
+  /// and returns a pointer to it.
+  #[cold]
+  unsafe fn grow(&mut self, layout: Layout, min: usize) -> *mut u8 {
+    let prev = self.chunks.last().map_or(0, |c| c.1.size());
+    let size = (prev * 2).clamp(PAGE, HUGE_PAGE).max(layout.size()).max(min);
+    let chunk = match Layout::from_size_align(size, layout.align().max(CHUNK_ALIGN)) {
+      Ok(l) => l,
+      Err(_) => AllocError::capacity_overflow(()).raise(),
+    };
+    let p = match NonNull::new(alloc(chunk)) { Some(p) => p, None => AllocError::alloc((), chunk).raise() };
+    self.chunks.push((p, chunk));
+    self.ptr = p.as_ptr().add(layout.size());
+    self.end = p.as_ptr().add(size);
+    p.as_ptr()
+  }
+}
+
+impl Default for Bump<'_> {
+  /// Equivalent to calling `Bump::new()`.
+  fn default() -> Self { Self::new() }
+}
+
+impl Drop for Bump<'_> {
+  fn drop(&mut self) {
+    for d in &self.0.get_mut().drops { unsafe { (d.drop)(d.ptr, d.len); } }
+  }
+}
+
+impl Drop for Inner {
+  fn drop(&mut self) {
+    // also runs if a destructor panics, leaking the values not dropped yet
+    for &(p, layout) in &self.chunks { unsafe { dealloc(p.as_ptr(), layout); } }
+  }
+}
@@ -0,0 +1,214 @@
+#[cfg(feature = "no_std")]
+use alloc::{boxed::Box, vec::Vec};
+#[cfg(not(feature = "no_std"))]
+use std::{boxed::Box, vec::Vec};
+use core::{mem::{self, MaybeUninit}, ptr::{self, NonNull}, slice, cell::UnsafeCell};
+
+// the first chunk takes a page, and later chunks double up to 2 MiB, unless a larger slice is requested
+pub(crate) const PAGE: usize = 4096;
+pub(crate) const HUGE_PAGE: usize = 2 << 20;
+
+/// An arena of objects of type `T`, which must be `Copy`.
+///
+/// `Copy` types have no destructor, so the arena doesn't keep track of which slots are initialized.
+/// Allocating just bumps a pointer, and dropping the arena just frees its chunks. Slices can be allocated too,
+/// by `alloc_slice_copy` and `alloc_extend`.
+///
+/// ## Example
+///
+/// ```
+/// use arena::DroplessArena;
+///
+/// #[derive(Clone, Copy, Debug, PartialEq)]
+/// struct Span { lo: u32, hi: u32 }
+///
+/// let a = DroplessArena::new();
+/// let x = a.alloc(Span { lo: 0, hi: 3 });
+/// let y = a.alloc_slice_copy(&[Span { lo: 3, hi: 5 }, Span { lo: 5, hi: 9 }]);
+/// x.hi = 2;
+/// assert_eq!((x.hi, y[1].lo), (2, 5));
+/// ```
+pub struct DroplessArena<T>(UnsafeCell<Inner<T>>);
+
+struct Inner<T> {
+  // the free slots of the current chunk
+  ptr: *mut T,
+  end: *mut T,
+  chunks: Vec<NonNull<[MaybeUninit<T>]>>,
+}
+
+unsafe impl<T: Send> Send for DroplessArena<T> {}
+
+impl<T: Copy> DroplessArena<T> {
+  /// Creates an empty arena, which doesn't allocate until the first allocation.
+  #[inline]
+  pub const fn new() -> Self {
+    DroplessArena(UnsafeCell::new(Inner { ptr: ptr::null_mut(), end: ptr::null_mut(), chunks: Vec::new() }))
+  }
+
+  /// Creates an arena whose first chunk can hold `n` elements.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::DroplessArena;
+  ///
+  /// let a = DroplessArena::with_capacity(10);
+  /// let x = a.alloc(0u64) as *mut u64;
+  /// for i in 1..10 { assert_eq!(a.alloc(i) as *mut u64, x.wrapping_add(i as usize)); }
+  /// ```
+  pub fn with_capacity(n: usize) -> Self {
+    let a = Self::new();
+    if n != 0 && mem::size_of::<T>() != 0 { unsafe { (*a.0.get()).push_chunk(n); } }
+    a
+  }
+
+  /// Allocates a value in the arena, and returns a mutable reference to it.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::DroplessArena;
+  ///
+  /// let a = DroplessArena::new();
+  /// let x = a.alloc(1);
+  /// let y = a.alloc(2);
+  /// *x += *y;
+  /// assert_eq!((*x, *y), (3, 2));
+  /// let b = DroplessArena::new();
+  /// assert_eq!(b.alloc(()), &mut ());
+  /// ```
+  #[inline]
+  pub fn alloc(&self, t: T) -> &mut T {
+    unsafe {
+      let p = self.claim(1);
+      p.write(t);
+      &mut *p
+    }
+  }
+
+  /// Copies a slice into the arena, and returns a mutable reference to the copy.
+  ///
+  /// The elements are kept contiguous, so if they don't fit in the current chunk, its free slots are left unused,
+  /// and a new chunk that can hold them is allocated. A slice larger than the next chunk would be gets
+  /// a chunk of its own instead, and the current chunk stays in use.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::DroplessArena;
+  ///
+  /// let a = DroplessArena::new();
+  /// let x = a.alloc_slice_copy(&[1, 2, 3]);
+  /// x[0] = 0;
+  /// assert_eq!(x, &[0, 2, 3]);
+  /// assert_eq!(a.alloc_slice_copy(&[]), &[]);
+  /// let big = vec![7; 10000];
+  /// assert_eq!(a.alloc_slice_copy(&big), &big[..]);
+  /// // still in the first chunk
+  /// assert_eq!(a.alloc(4) as *mut i32, x.as_mut_ptr().wrapping_add(3));
+  /// ```
+  #[inline]
+  pub fn alloc_slice_copy(&self, s: &[T]) -> &mut [T] {
+    if s.is_empty() { return &mut []; }
+    unsafe {
+      let p = self.claim(s.len());
+      p.copy_from_nonoverlapping(s.as_ptr(), s.len());
+      slice::from_raw_parts_mut(p, s.len())
+    }
+  }
+
+  /// Allocates all items of `iter` in the arena as a contiguous slice, and returns a mutable reference to it.
+  ///
+  /// If the iterator reports an exact length by its `size_hint`, the slots are reserved first, and the items are
+  /// written directly into them; should it yield fewer items, the remaining slots are left unused. Otherwise the items are
+  /// collected into a temporary `Vec` first. Either way, `iter` may itself allocate from the arena.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::DroplessArena;
+  ///
+  /// let a = DroplessArena::new();
+  /// assert_eq!(a.alloc_extend((0..5).map(|x| x * x)), &[0, 1, 4, 9, 16]);
+  /// assert_eq!(a.alloc_extend((0..10).filter(|x| x % 3 == 0)), &[0, 3, 6, 9]);
+  /// assert_eq!(a.alloc_extend((0..3).map(|x| *a.alloc(x) + 10)), &[10, 11, 12]);
+  /// assert_eq!(a.alloc_extend(None), &[]);
+  /// ```
+  pub fn alloc_extend(&self, iter: impl IntoIterator<Item = T>) -> &mut [T] {
+    let mut iter = iter.into_iter();
+    match iter.size_hint() {
+      (0, Some(0)) => &mut [],
+      (lo, Some(hi)) if lo == hi => unsafe {
+        let p = self.claim(lo);
+        let mut n = 0;
+        while n < lo {
+          match iter.next() {
+            Some(t) => { p.add(n).write(t); n += 1; }
+            None => break,
+          }
+        }
+        slice::from_raw_parts_mut(p, n)
+      }
+      _ => self.alloc_slice_copy(&iter.collect::<Vec<_>>()),
+    }
+  }
+
+  /// Reserves `n` contiguous slots, and returns a pointer to the first one.
+  #[inline]
+  unsafe fn claim(&self, n: usize) -> *mut T {
+    let inner = &mut *self.0.get();
+    if mem::size_of::<T>() == 0 { return NonNull::dangling().as_ptr(); }
+    if (inner.end as usize - inner.ptr as usize) / mem::size_of::<T>() < n {
+      return inner.grow(n);
+    }
+    let p = inner.ptr;
+    inner.ptr = p.add(n);
+    p
+  }
+}
+
+impl<T> Inner<T> {
+  /// Reserves `n` contiguous slots in a new chunk, and returns a pointer to the first one.
+  #[cold]
+  unsafe fn grow(&mut self, n: usize) -> *mut T {
+    // the current chunk is always the last one
+    let cap = match self.chunks.last() {
+      Some(c) => (c.len() * 2).min(HUGE_PAGE / mem::size_of::<T>()),
+      None => PAGE / mem::size_of::<T>(),
+    }.max(1);
+    if n > cap && !self.chunks.is_empty() {
+      let chunk = alloc_chunk(n);
+      self.chunks.insert(self.chunks.len() - 1, chunk);
+      return chunk.as_ptr() as *mut T;
+    }
+    let p = self.push_chunk(cap.max(n));
+    self.ptr = p.add(n);
+    p
+  }
+
+  /// Allocates a new current chunk that can hold `cap` elements, and returns a pointer to its start.
+  unsafe fn push_chunk(&mut self, cap: usize) -> *mut T {
+    let chunk = alloc_chunk(cap);
+    self.chunks.push(chunk);
+    self.ptr = chunk.as_ptr() as *mut T;
+    self.end = self.ptr.add(cap);
+    self.ptr
+  }
+}
+
+fn alloc_chunk<T>(cap: usize) -> NonNull<[MaybeUninit<T>]> {
+  unsafe { NonNull::new_unchecked(Box::into_raw(Box::<[T]>::new_uninit_slice(cap))) }
+}
+
+impl<T: Copy> Default for DroplessArena<T> {
+  /// Equivalent to calling `DroplessArena::<T>::new()`.
+  fn default() -> Self { Self::new() }
+}
+
+unsafe impl<#[may_dangle] T> Drop for DroplessArena<T> {
+  fn drop(&mut self) {
+    // the elements don't need to be dropped
+    for c in &self.0.get_mut().chunks { unsafe { drop(Box::from_raw(c.as_ptr())); } }
+  }
+}
@@ -0,0 +1,59 @@
+use core::{fmt, alloc::Layout};
+#[cfg(feature = "no_std")]
+use alloc::alloc::handle_alloc_error;
+#[cfg(not(feature = "no_std"))]
+use std::alloc::handle_alloc_error;
+
+/// The error returned by fallible allocation methods, when a new chunk can't be allocated.
+///
+/// It gives back the value that was to be allocated, so that the caller can retry or fall back to something else.
+pub struct AllocError<T = ()> {
+  value: T,
+  // `None` for capacity overflow
+  layout: Option<Layout>,
+}
+
+impl<T> AllocError<T> {
+  pub(crate) fn capacity_overflow(value: T) -> Self { Self { value, layout: None } }
+
+  pub(crate) fn alloc(value: T, layout: Layout) -> Self { Self { value, layout: Some(layout) } }
+
+  /// Returns the value that was to be allocated.
+  #[inline]
+  pub fn into_inner(self) -> T { self.value }
+
+  /// Returns the layout of the chunk that failed to be allocated, or `None` if its size overflowed.
+  #[inline]
+  pub fn layout(&self) -> Option<Layout> { self.layout }
+
+  /// Replace the value carried by this error.
+  #[inline]
+  pub(crate) fn with<U>(self, value: U) -> AllocError<U> { AllocError { value, layout: self.layout } }
+
+  /// Panic or abort, in the same way as infallible allocation does.
+  #[cold]
+  pub(crate) fn raise(self) -> ! {
+    match self.layout {
+      Some(layout) => handle_alloc_error(layout),
+      None => panic!("capacity overflow"),
+    }
+  }
+}
+
+impl<T> fmt::Debug for AllocError<T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("AllocError").field("layout", &self.layout).finish_non_exhaustive()
+  }
+}
+
+impl<T> fmt::Display for AllocError<T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.layout {
+      Some(layout) => write!(f, "memory allocation of {} bytes failed", layout.size()),
+      None => f.write_str("capacity overflow"),
+    }
+  }
+}
+
+#[cfg(not(feature = "no_std"))]
+impl<T> std::error::Error for AllocError<T> {}
@@ -0,0 +1,153 @@
+use core::{alloc::Layout, cell::Cell, marker::PhantomData, mem::MaybeUninit, ptr::{self, NonNull}, slice};
+use crate::AllocError;
+
+/// An arena of objects of type `T`, which allocates from a buffer given by the caller, and never from the heap.
+///
+/// Allocating fails once the buffer is full, since it never grows. Dropping the arena drops the objects,
+/// but leaves the buffer to its owner. The buffer can be a local array, or a `static` one on targets without a heap.
+/// Zero-sized types are supported, and the buffer length still limits how many of them can be allocated.
+///
+/// ## Example
+///
+/// ```
+/// use arena::FixedArena;
+/// use std::mem::MaybeUninit;
+///
+/// let mut buf = [MaybeUninit::uninit(); 4];
+/// let a = FixedArena::new(&mut buf);
+/// let x = a.alloc(1);
+/// let y = a.alloc(2);
+/// *x += *y;
+/// assert_eq!((*x, *y, a.spare_capacity()), (3, 2, 2));
+/// ```
+///
+/// A buffer in a `static`, for bare-metal targets:
+///
+/// ```
+/// use arena::FixedArena;
+/// use std::{mem::MaybeUninit, ptr};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Packet { id: u16, len: u16 }
+///
+/// static mut BUF: [MaybeUninit<Packet>; 8] = [const { MaybeUninit::uninit() }; 8];
+///
+/// // only this function ever touches `BUF`, and it is called once
+/// let a = FixedArena::new(unsafe { &mut *ptr::addr_of_mut!(BUF) });
+/// let ps: Vec<&mut Packet> = (0..8).map(|id| a.alloc(Packet { id, len: 64 })).collect();
+/// assert_eq!(ps[7], &Packet { id: 7, len: 64 });
+/// assert!(a.try_alloc(Packet { id: 8, len: 0 }).is_err());
+/// ```
+pub struct FixedArena<'buf, T> {
+  ptr: NonNull<T>,
+  cap: usize,
+  // the first `len` slots of the buffer are initialized
+  len: Cell<usize>,
+  _marker: PhantomData<(&'buf mut [MaybeUninit<T>], T)>,
+}
+
+impl<'buf, T> FixedArena<'buf, T> {
+  /// Creates an empty arena, which allocates from `buf`.
+  #[inline]
+  pub fn new(buf: &'buf mut [MaybeUninit<T>]) -> Self {
+    let cap = buf.len();
+    FixedArena { ptr: NonNull::from(buf).cast(), cap, len: Cell::new(0), _marker: PhantomData }
+  }
+
+  /// Allocates a value in the arena, and returns a mutable reference to it.
+  ///
+  /// ## Panics
+  ///
+  /// Panics if the buffer is full, like `ArrayVec::push`. Use `try_alloc` to handle it.
+  ///
+  /// ## Example
+  ///
+  /// ```should_panic
+  /// use arena::FixedArena;
+  /// use std::mem::MaybeUninit;
+  ///
+  /// let mut buf = [MaybeUninit::uninit(); 1];
+  /// let a = FixedArena::new(&mut buf);
+  /// a.alloc(0);
+  /// a.alloc(1); // panics
+  /// ```
+  #[inline]
+  pub fn alloc(&self, t: T) -> &mut T {
+    match self.try_alloc(t) { Ok(x) => x, Err(_) => panic!("FixedArena is full") }
+  }
+
+  /// Allocates a value in the arena, and returns a mutable reference to it, or an error holding the value
+  /// if the buffer is full. The error has the layout of `T`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::FixedArena;
+  /// use std::mem::MaybeUninit;
+  ///
+  /// let mut buf = [MaybeUninit::uninit(); 3];
+  /// let a = FixedArena::new(&mut buf);
+  /// for i in 0..3 { assert!(a.try_alloc(i).is_ok()); }
+  /// assert_eq!(a.try_alloc(3).unwrap_err().into_inner(), 3);
+  /// assert_eq!(a.len(), 3);
+  ///
+  /// // zero-sized types are counted too
+  /// let mut buf = [MaybeUninit::uninit(); 2];
+  /// let a = FixedArena::new(&mut buf);
+  /// assert!(a.try_alloc(()).is_ok() && a.try_alloc(()).is_ok() && a.try_alloc(()).is_err());
+  /// ```
+  pub fn try_alloc(&self, t: T) -> Result<&mut T, AllocError<T>> {
+    let len = self.len.get();
+    if len == self.cap { return Err(AllocError::alloc(t, Layout::new::<T>())); }
+    self.len.set(len + 1);
+    unsafe {
+      let p = self.ptr.as_ptr().add(len);
+      p.write(t);
+      Ok(&mut *p)
+    }
+  }
+
+  /// Returns the number of objects allocated in the arena.
+  #[inline]
+  pub fn len(&self) -> usize { self.len.get() }
+
+  /// Returns `true` if no object has been allocated in the arena.
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+  /// Returns the length of the buffer.
+  #[inline]
+  pub fn capacity(&self) -> usize { self.cap }
+
+  /// Returns the number of objects that can still be allocated.
+  #[inline]
+  pub fn spare_capacity(&self) -> usize { self.cap - self.len() }
+
+  /// Returns all objects allocated in the arena, in allocation order.
+  #[inline]
+  pub fn as_mut_slice(&mut self) -> &mut [T] { unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len()) } }
+}
+
+unsafe impl<T: Send> Send for FixedArena<'_, T> {}
+
+unsafe impl<#[may_dangle] T> Drop for FixedArena<'_, T> {
+  /// Drops the objects, without touching the rest of the buffer.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::FixedArena;
+  /// use std::{mem::MaybeUninit, rc::Rc};
+  ///
+  /// let r = Rc::new(());
+  /// let mut buf = [const { MaybeUninit::uninit() }; 10];
+  /// let a = FixedArena::new(&mut buf);
+  /// for _ in 0..6 { a.alloc(r.clone()); }
+  /// assert_eq!(Rc::strong_count(&r), 7);
+  /// drop(a);
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  /// ```
+  fn drop(&mut self) {
+    unsafe { ptr::drop_in_place(self.as_mut_slice()); }
+  }
+}
@@ -0,0 +1,211 @@
+use core::{cell::Cell, convert::TryFrom, mem, ptr::NonNull};
+use crate::{SimpleArena, slab::{Entry, NIL}};
+
+/// An arena of objects of type `T`, whose objects can be freed one by one, and whose handles detect reuse.
+///
+/// Like `SlabArena`, freed slots are reused by later allocations. Each slot also counts how many times it has been freed,
+/// and a `Handle` records that generation along with the index of the slot. Freeing bumps the generation, so a handle to
+/// a freed object never refers to the object reusing its slot. A slot whose generation reaches `u32::MAX` is never
+/// reused, rather than letting its generation wrap around.
+///
+/// ## Example
+///
+/// ```
+/// use arena::GenArena;
+///
+/// let mut a = GenArena::new();
+/// let x = a.alloc('x');
+/// a.free(x);
+/// // `y` reuses the slot of `x`, but `x` doesn't refer to it
+/// let y = a.alloc('y');
+/// assert_eq!((y.index(), y.generation()), (x.index(), x.generation() + 1));
+/// assert_eq!((a.get(x), a.get(y)), (None, Some(&'y')));
+/// assert_eq!(a.free(x), None);
+/// assert_eq!(a.len(), 1);
+/// ```
+pub struct GenArena<T> {
+  slots: SimpleArena<Slot<T>>,
+  // the index of the first vacant slot, or `NIL`
+  free: Cell<usize>,
+  len: Cell<usize>,
+}
+
+struct Slot<T> {
+  generation: u32,
+  entry: Entry<T>,
+}
+
+/// A handle to an object in a `GenArena`, returned by `GenArena::alloc`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Handle {
+  index: u32,
+  generation: u32,
+}
+
+impl Handle {
+  /// Returns the index of the slot of the object.
+  #[inline]
+  pub const fn index(self) -> u32 { self.index }
+
+  /// Returns the number of times the slot had been freed when the object was allocated.
+  #[inline]
+  pub const fn generation(self) -> u32 { self.generation }
+}
+
+impl<T> GenArena<T> {
+  /// Creates an empty arena.
+  #[inline]
+  pub fn new() -> Self { GenArena { slots: SimpleArena::new(), free: Cell::new(NIL), len: Cell::new(0) } }
+
+  /// Creates an arena whose first chunk can hold `n` objects.
+  #[inline]
+  pub fn with_capacity(n: usize) -> Self {
+    GenArena { slots: SimpleArena::with_capacity(n), free: Cell::new(NIL), len: Cell::new(0) }
+  }
+
+  /// Allocates a value in the arena, and returns its handle.
+  ///
+  /// The most recently freed slot is reused, otherwise a new slot is allocated.
+  ///
+  /// ## Panics
+  ///
+  /// Panics if a new slot is needed, and there are already `u32::MAX + 1` slots.
+  pub fn alloc(&self, t: T) -> Handle {
+    let i = self.free.get();
+    let handle = if i == NIL {
+      let index = u32::try_from(self.slots.len()).expect("too many slots");
+      self.slots.alloc(Slot { generation: 0, entry: Entry::Occupied(t) });
+      Handle { index, generation: 0 }
+    } else {
+      unsafe {
+        let slot = &mut *self.slots.get_ptr(i).unwrap().as_ptr();
+        match slot.entry {
+          Entry::Vacant(next) => self.free.set(next),
+          Entry::Occupied(_) => unreachable!(),
+        }
+        slot.entry = Entry::Occupied(t);
+        Handle { index: i as u32, generation: slot.generation }
+      }
+    };
+    self.len.set(self.len.get() + 1);
+    handle
+  }
+
+  /// Frees the object of `handle`, and returns it, or returns `None` if it is already freed.
+  ///
+  /// ## Example
+  ///
+  /// Every stale handle misses, however many times its slot is reused:
+  ///
+  /// ```
+  /// use arena::GenArena;
+  ///
+  /// let mut a = GenArena::new();
+  /// let keep = a.alloc(-1);
+  /// let mut stale = Vec::new();
+  /// let mut h = a.alloc(0);
+  /// for i in 1..100 {
+  ///   assert_eq!(a.free(h), Some(i - 1));
+  ///   stale.push(h);
+  ///   h = a.alloc(i);
+  ///   assert_eq!(h.index(), stale[0].index());
+  ///   assert!(stale.iter().all(|&s| a.get(s).is_none() && a.get_mut(s).is_none() && !a.contains(s)));
+  /// }
+  /// assert!(stale.iter().all(|&s| a.free(s).is_none()));
+  /// assert_eq!((a.get(h), a.get(keep), a.len()), (Some(&99), Some(&-1), 2));
+  /// ```
+  pub fn free(&mut self, handle: Handle) -> Option<T> {
+    let slot = unsafe { &mut *self.slots.get_ptr(handle.index as usize)?.as_ptr() };
+    if slot.generation != handle.generation { return None; }
+    if let Entry::Vacant(_) = slot.entry { return None; }
+    self.len.set(self.len.get() - 1);
+    let next = if slot.generation == u32::MAX { Entry::Vacant(NIL) } else {
+      slot.generation += 1;
+      Entry::Vacant(self.free.replace(handle.index as usize))
+    };
+    match mem::replace(&mut slot.entry, next) {
+      Entry::Occupied(t) => Some(t),
+      Entry::Vacant(_) => unreachable!(),
+    }
+  }
+
+  /// Returns a reference to the object of `handle`, or `None` if it is freed.
+  ///
+  /// The slot is found like `SimpleArena::get` finds an element, in `O(1)` time for the slots of the current chunk,
+  /// and `O(log chunk_count)` time for others. `get_mut` and `free` find it the same way.
+  #[inline]
+  pub fn get(&self, handle: Handle) -> Option<&T> {
+    match unsafe { &*self.slots.get_ptr(handle.index as usize)?.as_ptr() } {
+      Slot { generation, entry: Entry::Occupied(t) } if *generation == handle.generation => Some(t),
+      _ => None,
+    }
+  }
+
+  /// Returns a mutable reference to the object of `handle`, or `None` if it is freed.
+  #[inline]
+  pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+    match unsafe { &mut *self.slots.get_ptr(handle.index as usize)?.as_ptr() } {
+      Slot { generation, entry: Entry::Occupied(t) } if *generation == handle.generation => Some(t),
+      _ => None,
+    }
+  }
+
+  /// Returns `true` if the object of `handle` is not freed.
+  #[inline]
+  pub fn contains(&self, handle: Handle) -> bool { self.get(handle).is_some() }
+
+  /// Returns the number of objects that are not freed.
+  #[inline]
+  pub fn len(&self) -> usize { self.len.get() }
+
+  /// Returns `true` if every object is freed.
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+  /// Returns an iterator over the handles and objects that are not freed, in the order of their slots.
+  ///
+  /// The chunks of slots are walked in order, so this takes `O(n)` time for `n` slots. Objects allocated while
+  /// iterating are visited only if they reuse a slot not visited yet.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::GenArena;
+  ///
+  /// let mut a = GenArena::new();
+  /// let hs: Vec<_> = (0..6).map(|i| a.alloc(i)).collect();
+  /// a.free(hs[1]);
+  /// a.free(hs[4]);
+  /// let h = a.alloc(10);
+  /// let live: Vec<_> = a.iter().map(|(h, &x)| (h.index(), h.generation(), x)).collect();
+  /// assert_eq!(live, [(0, 0, 0), (2, 0, 2), (3, 0, 3), (4, 1, 10), (5, 0, 5)]);
+  /// assert_eq!(a.iter().count(), a.len());
+  /// assert_eq!(a.iter().nth(3).unwrap().0, h);
+  ///
+  /// // slots in many chunks
+  /// let mut a = GenArena::new();
+  /// let hs: Vec<_> = (0..1000).map(|i| a.alloc(i)).collect();
+  /// for &h in hs.iter().step_by(3) { a.free(h); }
+  /// assert!(a.iter().map(|(_, &x)| x).eq((0..1000).filter(|x| x % 3 != 0)));
+  /// ```
+  pub fn iter(&self) -> impl Iterator<Item = (Handle, &T)> {
+    // the next chunk, and the pointer, length and first index of the current one
+    let (mut k, mut chunk, mut start) = (0, (NonNull::dangling(), 0), 0);
+    (0..self.slots.len()).filter_map(move |i| {
+      while i - start >= chunk.1 {
+        start += chunk.1;
+        chunk = self.slots.chunk_ptr(k).unwrap();
+        k += 1;
+      }
+      match unsafe { &*chunk.0.as_ptr().add(i - start) } {
+        Slot { generation, entry: Entry::Occupied(t) } => Some((Handle { index: i as u32, generation: *generation }, t)),
+        _ => None,
+      }
+    })
+  }
+}
+
+impl<T> Default for GenArena<T> {
+  /// Equivalent to calling `GenArena::<T>::new()`.
+  fn default() -> Self { Self::new() }
+}
@@ -0,0 +1,103 @@
+use std::{vec::Vec, sync::Mutex};
+use core::{mem::ManuallyDrop, ops::Deref};
+use crate::Arena;
+
+/// A group of `Arena`s, one for each thread allocating from it, whose objects all live as long as the herd.
+///
+/// `get` hands out a `HerdMember`, which is an `Arena` used by one thread at a time. When the member is dropped,
+/// its arena is parked in the herd, and reused by the next `get`, possibly from another thread. The objects allocated
+/// from any member are dropped when the herd is dropped.
+///
+/// ## Example
+///
+/// ```
+/// use arena::Herd;
+/// use std::thread;
+///
+/// let h = Herd::new();
+/// let names: Vec<&String> = thread::scope(|s| {
+///   let hs: Vec<_> = (0..8).map(|t| {
+///     let h = &h;
+///     s.spawn(move || {
+///       let m = h.get();
+///       (0..50).map(|i| &*m.alloc(format!("{}-{}", t, i))).collect::<Vec<_>>()
+///     })
+///   }).collect();
+///   hs.into_iter().flat_map(|x| x.join().unwrap()).collect()
+/// });
+/// // the members are returned, but the strings are still owned by the herd
+/// assert_eq!(names.len(), 400);
+/// assert_eq!(names[0], "0-0");
+/// assert_eq!(names[399], "7-49");
+/// ```
+pub struct Herd<T>(Mutex<Vec<Arena<T>>>);
+
+/// An `Arena` borrowed from a `Herd`, see `Herd::get`.
+///
+/// It derefs to `Arena`, whose methods return references that live as long as the member. `alloc` and `alloc_extend`
+/// are also provided by the member itself, returning references that live as long as the herd.
+pub struct HerdMember<'h, T> {
+  herd: &'h Herd<T>,
+  arena: ManuallyDrop<Arena<T>>,
+}
+
+impl<T> Herd<T> {
+  /// Creates an empty herd, which has no arena until the first `get`.
+  #[inline]
+  pub const fn new() -> Self { Herd(Mutex::new(Vec::new())) }
+
+  /// Returns a member of the herd, reusing a parked arena if there is one.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Herd;
+  ///
+  /// let h = Herd::new();
+  /// let x = h.get().alloc(1);
+  /// let m = h.get();
+  /// let y = m.alloc(2);
+  /// // `m` reuses the arena `x` is allocated from
+  /// assert_eq!(m.len(), 2);
+  /// drop(m);
+  /// assert_eq!((*x, *y), (1, 2));
+  /// ```
+  pub fn get(&self) -> HerdMember<'_, T> {
+    let arena = self.0.lock().unwrap_or_else(|e| e.into_inner()).pop().unwrap_or_default();
+    HerdMember { herd: self, arena: ManuallyDrop::new(arena) }
+  }
+}
+
+impl<'h, T> HerdMember<'h, T> {
+  /// Allocates a value in the arena, and returns a mutable reference to it, which lives as long as the herd.
+  #[inline]
+  pub fn alloc(&self, t: T) -> &'h mut T {
+    // the arena is never dropped or accessed by `&mut` before the herd is dropped
+    unsafe { &mut *(self.arena.alloc(t) as *mut T) }
+  }
+
+  /// Allocates all items of `iter` as a contiguous slice, and returns a mutable reference to it,
+  /// which lives as long as the herd, see `Arena::alloc_extend`.
+  #[inline]
+  pub fn alloc_extend(&self, iter: impl IntoIterator<Item = T>) -> &'h mut [T] {
+    unsafe { &mut *(self.arena.alloc_extend(iter) as *mut [T]) }
+  }
+}
+
+impl<T> Deref for HerdMember<'_, T> {
+  type Target = Arena<T>;
+  #[inline]
+  fn deref(&self) -> &Arena<T> { &self.arena }
+}
+
+impl<T> Drop for HerdMember<'_, T> {
+  fn drop(&mut self) {
+    let arena = unsafe { ManuallyDrop::take(&mut self.arena) };
+    self.herd.0.lock().unwrap_or_else(|e| e.into_inner()).push(arena);
+  }
+}
+
+impl<T> Default for Herd<T> {
+  /// Equivalent to calling `Herd::<T>::new()`.
+  fn default() -> Self { Self::new() }
+}
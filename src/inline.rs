@@ -0,0 +1,101 @@
+use core::{cell::{Cell, UnsafeCell}, mem::MaybeUninit, ptr};
+use crate::SimpleArena;
+
+/// An arena of objects of type `T`, whose first `N` objects are stored inline, without allocating.
+///
+/// Once the inline slots are full, later objects are allocated in a `SimpleArena`. The inline objects stay where they
+/// are, so their references remain valid. Small arenas never touch the allocator, at the cost of a larger struct.
+///
+/// ## Example
+///
+/// ```
+/// use arena::InlineArena;
+///
+/// let a = InlineArena::<String, 2>::new();
+/// let x = a.alloc("x".to_string());
+/// let y = a.alloc("y".to_string());
+/// assert!(!a.spilled());
+/// let z = a.alloc("z".to_string());
+/// x.push('!');
+/// assert!(a.spilled());
+/// assert_eq!((x.as_str(), y.as_str(), z.as_str(), a.len()), ("x!", "y", "z", 3));
+/// ```
+///
+/// Allocating up to `N` objects makes no allocator call:
+///
+/// ```
+/// use arena::InlineArena;
+/// use std::{alloc::{GlobalAlloc, Layout, System}, sync::atomic::{AtomicUsize, Ordering}};
+///
+/// struct Counting;
+/// static CALLS: AtomicUsize = AtomicUsize::new(0);
+/// unsafe impl GlobalAlloc for Counting {
+///   unsafe fn alloc(&self, l: Layout) -> *mut u8 { CALLS.fetch_add(1, Ordering::Relaxed); System.alloc(l) }
+///   unsafe fn dealloc(&self, p: *mut u8, l: Layout) { System.dealloc(p, l) }
+/// }
+/// #[global_allocator]
+/// static GLOBAL: Counting = Counting;
+///
+/// let before = CALLS.load(Ordering::Relaxed);
+/// let a = InlineArena::<u64, 16>::new();
+/// for i in 0..16 { a.alloc(i); }
+/// drop(a);
+/// assert_eq!(CALLS.load(Ordering::Relaxed), before);
+/// let a = InlineArena::<u64, 16>::new();
+/// for i in 0..17 { a.alloc(i); }
+/// assert!(CALLS.load(Ordering::Relaxed) > before);
+/// ```
+pub struct InlineArena<T, const N: usize> {
+  inline: UnsafeCell<[MaybeUninit<T>; N]>,
+  // the first `len` inline slots are initialized
+  len: Cell<usize>,
+  heap: SimpleArena<T>,
+}
+
+impl<T, const N: usize> InlineArena<T, N> {
+  /// Creates an empty arena.
+  #[inline]
+  pub const fn new() -> Self {
+    InlineArena { inline: UnsafeCell::new([const { MaybeUninit::uninit() }; N]), len: Cell::new(0), heap: SimpleArena::new() }
+  }
+
+  /// Allocates a value in the arena, and returns a mutable reference to it.
+  ///
+  /// The value is stored inline if there is a free inline slot, otherwise in the heap.
+  #[inline]
+  pub fn alloc(&self, t: T) -> &mut T {
+    let len = self.len.get();
+    if len == N { return self.heap.alloc(t); }
+    self.len.set(len + 1);
+    unsafe {
+      let p = (self.inline.get() as *mut T).add(len);
+      p.write(t);
+      &mut *p
+    }
+  }
+
+  /// Returns the number of objects allocated in the arena.
+  #[inline]
+  pub fn len(&self) -> usize { self.len.get() + self.heap.len() }
+
+  /// Returns `true` if no object has been allocated in the arena.
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+  /// Returns `true` if some objects are allocated in the heap, because the inline slots are full.
+  #[inline]
+  pub fn spilled(&self) -> bool { !self.heap.is_empty() }
+}
+
+impl<T, const N: usize> Default for InlineArena<T, N> {
+  /// Equivalent to calling `InlineArena::<T, N>::new()`.
+  fn default() -> Self { Self::new() }
+}
+
+unsafe impl<#[may_dangle] T, const N: usize> Drop for InlineArena<T, N> {
+  /// Drops the inline objects, and then the objects in the heap.
+  fn drop(&mut self) {
+    let len = self.len.get();
+    unsafe { ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.inline.get() as *mut T, len)); }
+  }
+}
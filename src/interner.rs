@@ -0,0 +1,192 @@
+use std::{collections::{HashMap, hash_map::RandomState}, hash::BuildHasher, vec::Vec, cell::UnsafeCell, convert::TryFrom};
+use crate::StrArena;
+
+/// A string interner, which keeps one copy of each distinct string in a `StrArena`.
+///
+/// Interning a string returns a `Symbol`, which is 4 bytes, `Copy`, and compared or hashed in constant time.
+/// Equal strings always get the same symbol, and `resolve` gives the interned copy back. References to the copies
+/// live as long as the interner itself.
+///
+/// ## Example
+///
+/// ```
+/// use arena::Interner;
+///
+/// let i = Interner::new();
+/// let words: Vec<_> = "to be or not to be".split(' ').map(|w| i.intern(w)).collect();
+/// assert_eq!(i.len(), 4);
+/// assert_eq!((words[0], words[1]), (words[4], words[5]));
+/// assert_ne!(words[0], words[1]);
+/// assert_eq!(i.resolve(words[3]), "not");
+/// let be: &str = i.intern_str("be");
+/// assert_eq!(be.as_ptr(), i.resolve(words[1]).as_ptr());
+/// ```
+pub struct Interner<S = RandomState>(UnsafeCell<Inner<S>>);
+
+struct Inner<S> {
+  // the strings point into `arena`, which never moves or frees them until it is dropped
+  map: HashMap<&'static str, Symbol, S>,
+  strs: Vec<&'static str>,
+  arena: StrArena,
+}
+
+/// A string interned by an `Interner`, see `Interner::intern`.
+///
+/// Symbols are numbered from 0 in the order their strings are first interned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(u32);
+
+impl Symbol {
+  /// Returns the number of this symbol.
+  #[inline]
+  pub const fn as_u32(self) -> u32 { self.0 }
+}
+
+impl Interner {
+  /// Creates an empty interner.
+  #[inline]
+  pub fn new() -> Self { Self::with_hasher(RandomState::new()) }
+}
+
+impl<S: BuildHasher> Interner<S> {
+  /// Creates an empty interner, which hashes strings by `hasher`.
+  ///
+  /// ## Example
+  ///
+  /// Every string hashes to the same value, but equal strings are still told apart from different ones:
+  ///
+  /// ```
+  /// use arena::Interner;
+  /// use std::hash::{BuildHasherDefault, Hasher};
+  ///
+  /// #[derive(Default)]
+  /// struct Collide;
+  /// impl Hasher for Collide {
+  ///   fn finish(&self) -> u64 { 0 }
+  ///   fn write(&mut self, _: &[u8]) {}
+  /// }
+  ///
+  /// let i = Interner::with_hasher(BuildHasherDefault::<Collide>::default());
+  /// let syms: Vec<_> = (0..100).map(|x| i.intern(&x.to_string())).collect();
+  /// assert!((0..100).all(|x| i.intern(&x.to_string()) == syms[x]));
+  /// assert!((0..100).all(|x| i.resolve(syms[x]) == x.to_string()));
+  /// assert_eq!(i.len(), 100);
+  /// ```
+  #[inline]
+  pub fn with_hasher(hasher: S) -> Self {
+    Interner(UnsafeCell::new(Inner { map: HashMap::with_hasher(hasher), strs: Vec::new(), arena: StrArena::new() }))
+  }
+
+  /// Interns `s`, and returns its symbol.
+  ///
+  /// If an equal string has been interned, its symbol is returned, otherwise `s` is copied into the arena.
+  ///
+  /// ## Panics
+  ///
+  /// Panics if there are already `u32::MAX + 1` symbols.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Interner;
+  ///
+  /// let i = Interner::new();
+  /// let syms: Vec<_> = (0..2000).map(|x| i.intern(&format!("s{}", x % 500))).collect();
+  /// assert_eq!(i.len(), 500);
+  /// assert!(syms.iter().enumerate().all(|(x, s)| s.as_u32() as usize == x % 500));
+  /// assert_eq!(i.intern(""), i.intern(""));
+  /// ```
+  ///
+  /// A very large number of symbols, each resolving to its own string:
+  ///
+  /// ```
+  /// use arena::Interner;
+  ///
+  /// // fewer under Miri, which is much slower
+  /// let n = if cfg!(miri) { 1_000 } else { 1_000_000 };
+  /// let i = Interner::new();
+  /// let syms: Vec<_> = (0..n).map(|x| i.intern(&x.to_string())).collect();
+  /// assert_eq!(i.len(), n);
+  /// assert!(syms.iter().enumerate().all(|(x, &s)| s.as_u32() as usize == x && i.resolve(s) == x.to_string()));
+  /// assert!((0..n).step_by(97).all(|x| i.intern(&x.to_string()) == syms[x]));
+  /// ```
+  pub fn intern(&self, s: &str) -> Symbol {
+    let inner = unsafe { &mut *self.0.get() };
+    if let Some(&sym) = inner.map.get(s) { return sym; }
+    let sym = Symbol(u32::try_from(inner.strs.len()).expect("too many symbols"));
+    let s: &'static str = unsafe { &*(inner.arena.alloc_str(s) as *const str) };
+    inner.strs.push(s);
+    inner.map.insert(s, sym);
+    sym
+  }
+
+  /// Interns `s`, and returns the interned copy, see `intern`.
+  #[inline]
+  pub fn intern_str(&self, s: &str) -> &str { self.resolve(self.intern(s)) }
+
+  /// Returns the symbol of `s`, or `None` if it hasn't been interned.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Interner;
+  ///
+  /// let i = Interner::new();
+  /// let x = i.intern("x");
+  /// assert_eq!((i.get("x"), i.get("y")), (Some(x), None));
+  /// ```
+  #[inline]
+  pub fn get(&self, s: &str) -> Option<Symbol> {
+    let Inner { map, .. } = unsafe { &*self.0.get() };
+    map.get(s).copied()
+  }
+}
+
+impl<S> Interner<S> {
+  /// Returns the string interned as `sym`.
+  ///
+  /// ## Panics
+  ///
+  /// Panics if the number of `sym` is not less than `len()`, which never happens for a symbol returned by this interner.
+  /// A symbol of another interner whose number is small enough resolves to an unrelated string instead.
+  #[inline]
+  pub fn resolve(&self, sym: Symbol) -> &str {
+    let Inner { strs, .. } = unsafe { &*self.0.get() };
+    strs[sym.0 as usize]
+  }
+
+  /// Returns the number of distinct strings interned.
+  #[inline]
+  pub fn len(&self) -> usize {
+    let Inner { strs, .. } = unsafe { &*self.0.get() };
+    strs.len()
+  }
+
+  /// Returns `true` if no string has been interned.
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+  /// Returns an iterator over all symbols and their strings, in the order they are first interned.
+  ///
+  /// Strings interned while iterating are not yielded.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::Interner;
+  ///
+  /// let i = Interner::new();
+  /// for w in "a rose is a rose".split(' ') { i.intern(w); }
+  /// for (_, s) in i.iter() { i.intern(&s.repeat(2)); }
+  /// let all: Vec<_> = i.iter().map(|(sym, s)| (sym.as_u32(), s)).collect();
+  /// assert_eq!(all, [(0, "a"), (1, "rose"), (2, "is"), (3, "aa"), (4, "roserose"), (5, "isis")]);
+  /// ```
+  pub fn iter(&self) -> impl Iterator<Item = (Symbol, &str)> {
+    (0..self.len() as u32).map(move |i| (Symbol(i), self.resolve(Symbol(i))))
+  }
+}
+
+impl<S: Default + BuildHasher> Default for Interner<S> {
+  /// Equivalent to calling `Interner::with_hasher(S::default())`.
+  fn default() -> Self { Self::with_hasher(S::default()) }
+}
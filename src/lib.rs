@@ -3,6 +3,7 @@
 #![feature(ptr_internals)]
 #![feature(dropck_eyepatch)]
 #![feature(vec_into_raw_parts)]
+#![feature(allocator_api)]
 #![feature(external_doc)]
 #![deny(missing_docs)]
 #![cfg_attr(feature = "no_std", no_std)]
@@ -14,5 +15,45 @@ extern crate alloc;
 pub mod simple;
 /// Providing struct `Arena`.
 pub mod arena;
+/// Providing struct `LockedArena`.
+pub mod locked;
+/// Providing struct `InlineArena`.
+pub mod inline;
+/// Providing struct `FixedArena`.
+pub mod fixed;
+/// Providing struct `SyncArena`.
+#[cfg(not(feature = "no_std"))]
+pub mod sync;
+/// Providing struct `Herd`.
+#[cfg(not(feature = "no_std"))]
+pub mod herd;
+/// Providing struct `DroplessArena`.
+pub mod dropless;
+/// Providing struct `Bump`.
+pub mod bump;
+/// Providing struct `ArenaVec`.
+pub mod vec;
+/// Providing struct `ArenaString`.
+pub mod string;
+/// Providing struct `SlabArena`.
+pub mod slab;
+/// Providing struct `GenArena`.
+pub mod generational;
+/// Providing struct `StrArena`.
+pub mod str_arena;
+/// Providing struct `Interner`.
+#[cfg(not(feature = "no_std"))]
+pub mod interner;
+/// Providing struct `BrandedArena`.
+pub mod branded;
+/// Providing struct `ArenaBox`.
+pub mod boxed;
+/// Providing struct `ArenaRc`.
+pub mod rc;
+/// Providing struct `AllocError`.
+pub mod error;
+mod reserve;
 
-pub use crate::{simple::SimpleArena, arena::Arena};
\ No newline at end of file
+pub use crate::{simple::SimpleArena, arena::Arena, locked::LockedArena, dropless::DroplessArena, fixed::FixedArena, inline::InlineArena, bump::Bump, vec::ArenaVec, string::ArenaString, str_arena::StrArena, slab::{SlabArena, SlotId}, generational::{GenArena, Handle}, branded::BrandedArena, boxed::ArenaBox, rc::ArenaRc, error::AllocError};
+#[cfg(not(feature = "no_std"))]
+pub use crate::{sync::SyncArena, herd::{Herd, HerdMember}, interner::{Interner, Symbol}};
\ No newline at end of file
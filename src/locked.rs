@@ -0,0 +1,140 @@
+#[cfg(not(feature = "no_std"))]
+use std::sync::{Mutex, MutexGuard};
+#[cfg(feature = "no_std")]
+use core::{hint, sync::atomic::{AtomicBool, Ordering}};
+use core::cell::UnsafeCell;
+use crate::Arena;
+
+/// An `Arena` behind a lock, which can be shared between threads.
+///
+/// Each allocation takes the lock only while the value is moved into the arena. The returned reference stays valid
+/// after the lock is released, because the arena never moves its elements. The lock is a `std::sync::Mutex`,
+/// or a spin lock with the `no_std` feature.
+///
+/// ## Example
+///
+/// Several threads allocating into one arena, without losing or duplicating a slot:
+///
+/// ```
+/// use arena::LockedArena;
+/// use std::{collections::HashSet, sync::Arc, thread};
+///
+/// let a = Arc::new(LockedArena::new());
+/// let hs: Vec<_> = (0..4).map(|t| {
+///   let a = a.clone();
+///   thread::spawn(move || {
+///     let xs: Vec<&mut (usize, usize)> = (0..200).map(|i| a.alloc((t, i))).collect();
+///     assert!(xs.iter().enumerate().all(|(i, x)| **x == (t, i)));
+///     xs.iter().map(|x| *x as *const _ as usize).collect::<Vec<_>>()
+///   })
+/// }).collect();
+/// let addrs: HashSet<usize> = hs.into_iter().flat_map(|h| h.join().unwrap()).collect();
+/// assert_eq!(addrs.len(), 800);
+/// let mut all = Arc::try_unwrap(a).ok().unwrap().into_inner().into_vec();
+/// all.sort();
+/// assert!(all.iter().enumerate().all(|(i, &x)| x == (i / 200, i % 200)));
+/// ```
+pub struct LockedArena<T> {
+  lock: Lock,
+  arena: UnsafeCell<Arena<T>>,
+}
+
+unsafe impl<T: Send> Send for LockedArena<T> {}
+unsafe impl<T: Send> Sync for LockedArena<T> {}
+
+#[cfg(not(feature = "no_std"))]
+struct Lock(Mutex<()>);
+
+#[cfg(not(feature = "no_std"))]
+impl Lock {
+  const fn new() -> Self { Lock(Mutex::new(())) }
+
+  #[inline]
+  fn lock(&self) -> MutexGuard<'_, ()> {
+    // the arena is still consistent if a thread panics while holding the lock
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+#[cfg(feature = "no_std")]
+struct Lock(AtomicBool);
+
+#[cfg(feature = "no_std")]
+struct LockGuard<'a>(&'a AtomicBool);
+
+#[cfg(feature = "no_std")]
+impl Lock {
+  const fn new() -> Self { Lock(AtomicBool::new(false)) }
+
+  #[inline]
+  fn lock(&self) -> LockGuard<'_> {
+    while self.0.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+      while self.0.load(Ordering::Relaxed) { hint::spin_loop(); }
+    }
+    LockGuard(&self.0)
+  }
+}
+
+#[cfg(feature = "no_std")]
+impl Drop for LockGuard<'_> {
+  fn drop(&mut self) { self.0.store(false, Ordering::Release); }
+}
+
+impl<T> LockedArena<T> {
+  /// Creates an empty arena.
+  #[inline]
+  pub fn new() -> Self { Self::from_arena(Arena::new()) }
+
+  /// Puts `arena` behind a lock.
+  #[inline]
+  pub fn from_arena(arena: Arena<T>) -> Self { LockedArena { lock: Lock::new(), arena: UnsafeCell::new(arena) } }
+
+  /// Allocates a value in the arena, and returns a mutable reference to it.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::LockedArena;
+  ///
+  /// let a = LockedArena::new();
+  /// let x = a.alloc(1);
+  /// let y = a.alloc(2);
+  /// *x += *y;
+  /// assert_eq!((*x, *y, a.len()), (3, 2, 2));
+  /// ```
+  #[inline]
+  pub fn alloc(&self, t: T) -> &mut T {
+    let _guard = self.lock.lock();
+    // the slot is never handed out again, and never moves until the arena is dropped or accessed by `&mut`
+    unsafe { &mut *((*self.arena.get()).alloc(t) as *mut T) }
+  }
+
+  /// Returns the number of objects allocated in the arena.
+  #[inline]
+  pub fn len(&self) -> usize {
+    let _guard = self.lock.lock();
+    unsafe { (*self.arena.get()).len() }
+  }
+
+  /// Returns `true` if no object has been allocated in the arena.
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+  /// Returns a mutable reference to the arena, which needs no locking.
+  #[inline]
+  pub fn get_mut(&mut self) -> &mut Arena<T> { self.arena.get_mut() }
+
+  /// Consumes the lock, and returns the arena.
+  #[inline]
+  pub fn into_inner(self) -> Arena<T> { self.arena.into_inner() }
+}
+
+impl<T> Default for LockedArena<T> {
+  /// Equivalent to calling `LockedArena::<T>::new()`.
+  fn default() -> Self { Self::new() }
+}
+
+impl<T> From<Arena<T>> for LockedArena<T> {
+  /// Equivalent to calling `LockedArena::from_arena(arena)`.
+  fn from(arena: Arena<T>) -> Self { Self::from_arena(arena) }
+}
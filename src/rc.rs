@@ -0,0 +1,109 @@
+use core::{fmt, cell::{Cell, UnsafeCell}, marker::PhantomData, mem::{ManuallyDrop, MaybeUninit}, ops::Deref, ptr::NonNull};
+
+/// A value in an `Arena` together with its reference count, which is the element type of arenas used by `Arena::alloc_rc`.
+///
+/// The value is never dropped by the arena, only by the last `ArenaRc` pointing to it.
+pub struct RcSlot<T> {
+  count: Cell<usize>,
+  value: UnsafeCell<ManuallyDrop<T>>,
+}
+
+/// A reference-counted pointer to a value in an `Arena`, returned by `Arena::alloc_rc`.
+///
+/// The count is stored next to the value in the arena, so creating an `ArenaRc` doesn't allocate on its own,
+/// and cloning it just increments the count. When the last `ArenaRc` pointing to a value is dropped, the value is dropped,
+/// but its memory stays in the arena until the arena is dropped.
+///
+/// Like `Rc`, values referring to each other in a cycle are never dropped, and the arena doesn't drop them either,
+/// so resources held by them are leaked.
+///
+/// ## Example
+///
+/// A child shared by two parents is dropped after both parents:
+///
+/// ```
+/// use arena::{Arena, ArenaRc};
+/// use std::cell::{Cell, RefCell};
+///
+/// struct Node<'a> { name: char, children: Vec<ArenaRc<'a, Node<'a>>>, drops: &'a RefCell<String> }
+/// impl Drop for Node<'_> {
+///   fn drop(&mut self) { self.drops.borrow_mut().push(self.name); }
+/// }
+///
+/// let drops = RefCell::new(String::new());
+/// let a = Arena::new();
+/// let child = a.alloc_rc(Node { name: 'c', children: vec![], drops: &drops });
+/// let p = a.alloc_rc(Node { name: 'p', children: vec![child.clone()], drops: &drops });
+/// let q = a.alloc_rc(Node { name: 'q', children: vec![child.clone()], drops: &drops });
+/// assert_eq!(ArenaRc::strong_count(&child), 3);
+/// drop(child);
+/// drop(p);
+/// assert_eq!(*drops.borrow(), "p");
+/// assert_eq!(ArenaRc::strong_count(&q.children[0]), 1);
+/// drop(q);
+/// assert_eq!(*drops.borrow(), "pqc");
+///
+/// // a cycle is leaked
+/// struct Cyclic<'a>(RefCell<Option<ArenaRc<'a, Cyclic<'a>>>>, &'a Cell<usize>);
+/// impl Drop for Cyclic<'_> {
+///   fn drop(&mut self) { self.1.set(self.1.get() + 1); }
+/// }
+///
+/// let count = Cell::new(0);
+/// {
+///   let a = Arena::new();
+///   let x = a.alloc_rc(Cyclic(RefCell::new(None), &count));
+///   let y = a.alloc_rc(Cyclic(RefCell::new(Some(x.clone())), &count));
+///   *x.0.borrow_mut() = Some(y);
+/// }
+/// assert_eq!(count.get(), 0);
+/// ```
+pub struct ArenaRc<'a, T>(NonNull<RcSlot<T>>, PhantomData<&'a RcSlot<T>>);
+
+impl<'a, T> ArenaRc<'a, T> {
+  #[inline]
+  pub(crate) fn new(slot: &'a mut MaybeUninit<RcSlot<T>>, t: T) -> Self {
+    let slot = slot.write(RcSlot { count: Cell::new(1), value: UnsafeCell::new(ManuallyDrop::new(t)) });
+    ArenaRc(NonNull::from(slot), PhantomData)
+  }
+
+  /// Returns the number of `ArenaRc`s pointing to the same value as `this`.
+  #[inline]
+  pub fn strong_count(this: &Self) -> usize { this.slot().count.get() }
+
+  /// Returns `true` if `this` and `other` point to the same value.
+  #[inline]
+  pub fn ptr_eq(this: &Self, other: &Self) -> bool { this.0 == other.0 }
+
+  #[inline]
+  fn slot(&self) -> &RcSlot<T> { unsafe { self.0.as_ref() } }
+}
+
+impl<T> Clone for ArenaRc<'_, T> {
+  #[inline]
+  fn clone(&self) -> Self {
+    let count = &self.slot().count;
+    count.set(count.get().checked_add(1).expect("reference count overflow"));
+    ArenaRc(self.0, PhantomData)
+  }
+}
+
+impl<T> Deref for ArenaRc<'_, T> {
+  type Target = T;
+  #[inline]
+  fn deref(&self) -> &T { unsafe { &*self.slot().value.get() } }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArenaRc<'_, T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { fmt::Debug::fmt(&**self, f) }
+}
+
+impl<T> Drop for ArenaRc<'_, T> {
+  fn drop(&mut self) {
+    let slot = self.slot();
+    slot.count.set(slot.count.get() - 1);
+    if slot.count.get() == 0 {
+      unsafe { ManuallyDrop::drop(&mut *slot.value.get()); }
+    }
+  }
+}
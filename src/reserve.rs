@@ -0,0 +1,275 @@
+#[cfg(feature = "no_std")]
+use alloc::vec::Vec;
+#[cfg(not(feature = "no_std"))]
+use std::vec::Vec;
+use core::{mem, ptr, slice, cell::UnsafeCell};
+
+/// The state of the `Reserve` or `ReserveSlot` writing past the end of the current chunk of an arena.
+#[derive(Clone, Copy)]
+pub(crate) struct Tail {
+  depth: usize,
+  // the actual end of the slots of the current chunk not reserved by `alloc_uninit`
+  pub(crate) end: usize,
+  // the number of elements written after the initialized part of the current chunk, which are not counted yet
+  pub(crate) pending: usize,
+  // whether it is a `ReserveSlot`, whose slot can't be moved
+  pinned: bool,
+}
+
+/// The bookkeeping an arena keeps for its live `Reserve`s and `ReserveSlot`s.
+pub(crate) struct Tails<T> {
+  // the `Reserve` or `ReserveSlot` writing past the end of the current chunk, which sets the end to the length
+  // of the chunk meanwhile, so that any other allocation calls `settle` first
+  pub(crate) tail: Option<Tail>,
+  // the number of live `Reserve`s and `ReserveSlot`s, which identify them
+  depth: usize,
+  // the elements of `Reserve`s moved out of the current chunk by `settle`, innermost last
+  moved: Vec<Vec<T>>,
+}
+
+impl<T> Tails<T> {
+  #[inline]
+  pub(crate) const fn new() -> Self { Tails { tail: None, depth: 0, moved: Vec::new() } }
+}
+
+/// The chunks of an arena, as seen by `Reserve` and `ReserveSlot`, implemented by the `Inner` of `Arena` and `SimpleArena`.
+///
+/// The current chunk holds `len()` elements, followed by free slots up to `end()`. The slots after those are
+/// reserved by `alloc_uninit`.
+pub(crate) trait Chunks {
+  type Item;
+
+  fn tails(&mut self) -> &mut Tails<Self::Item>;
+
+  /// Returns the number of elements in the current chunk.
+  fn len(&self) -> usize;
+
+  /// Sets the number of elements in the current chunk, without dropping or initializing anything.
+  unsafe fn set_len(&mut self, len: usize);
+
+  /// Returns the end of the free slots of the current chunk.
+  fn end(&mut self) -> &mut usize;
+
+  /// Returns a pointer to the first slot of the current chunk.
+  fn as_mut_ptr(&mut self) -> *mut Self::Item;
+
+  /// Returns the number of elements in the retired chunks.
+  fn retired_len(&self) -> usize;
+
+  /// Returns `ends`, where `ends[k]` is the number of elements in the retired chunks up to the `k`-th one.
+  fn ends(&mut self) -> &mut [usize];
+
+  /// Makes room for `additional` elements in the current chunk, retiring it if needed. Nothing is settled.
+  fn reserve(&mut self, additional: usize);
+
+  /// Moves the elements written by the `Reserve` past the end of the current chunk, which is full, to a new chunk.
+  ///
+  /// Only called if a `Reserve` writes more elements than it reserved, which `SimpleArena` never does.
+  fn grow_tail(&mut self);
+
+  /// Stops counting the elements of the `k`-th retired chunk after the first `len`, and those of all later retired
+  /// chunks, without dropping them. `ends` is already updated.
+  unsafe fn forget_retired(&mut self, k: usize, len: usize);
+
+  /// Lets the `Reserve` or `ReserveSlot` of `depth + 1` write past the end of the current chunk, and returns `depth + 1`.
+  #[inline]
+  fn take_tail(&mut self, pinned: bool) -> usize {
+    let len = self.len();
+    let end = mem::replace(self.end(), len);
+    let tails = self.tails();
+    debug_assert!(tails.tail.is_none());
+    tails.depth += 1;
+    tails.tail = Some(Tail { depth: tails.depth, end, pending: 0, pinned });
+    tails.depth
+  }
+
+  /// Ends the `Reserve` or `ReserveSlot` of `depth`, and returns its `Tail` if it still writes past the end of
+  /// the current chunk.
+  #[inline]
+  fn release_tail(&mut self, depth: usize) -> Option<Tail> {
+    let tails = self.tails();
+    debug_assert_eq!(tails.depth, depth);
+    tails.depth -= 1;
+    match tails.tail {
+      Some(tail) if tail.depth == depth => {
+        tails.tail = None;
+        *self.end() = tail.end;
+        Some(tail)
+      }
+      _ => None,
+    }
+  }
+
+  /// Gives the end of the current chunk back to the arena, before allocating anything else.
+  #[inline]
+  fn settle(&mut self) {
+    if self.tails().tail.is_some() { self.settle_tail(); }
+  }
+
+  /// Implementation of `settle`: the slot of a `ReserveSlot` is counted, even though it is not initialized yet,
+  /// and the elements written by a `Reserve` are moved to `moved`, since no reference to them has been returned yet.
+  #[cold]
+  fn settle_tail(&mut self) {
+    let tail = self.tails().tail.take().unwrap();
+    let len = self.len();
+    if tail.pinned {
+      unsafe { self.set_len(len + 1); }
+    } else {
+      let mut v = Vec::with_capacity(tail.pending);
+      unsafe {
+        ptr::copy_nonoverlapping(self.as_mut_ptr().add(len), v.as_mut_ptr(), tail.pending);
+        v.set_len(tail.pending);
+      }
+      self.tails().moved.push(v);
+    }
+    *self.end() = tail.end;
+  }
+
+  /// Stops counting the `i`-th element and all elements after it, without dropping them.
+  ///
+  /// This is called when the user code of a `ReserveSlot` panics after its slot was counted, so the `i`-th element
+  /// is not initialized, and all later elements were allocated by the user code. Their slots are never reused,
+  /// since references to them may still exist.
+  #[cold]
+  fn forget_from(&mut self, i: usize) {
+    let retired = self.retired_len();
+    unsafe {
+      if i >= retired {
+        self.set_len(i - retired);
+      } else {
+        let ends = self.ends();
+        let k = ends.partition_point(|&e| e <= i);
+        let start = if k == 0 { 0 } else { ends[k - 1] };
+        for e in &mut ends[k..] { *e = i; }
+        self.forget_retired(k, i - start);
+        self.set_len(0);
+      }
+    }
+    *self.end() = self.len();
+  }
+}
+
+/// A contiguous slice being filled at the end of the current chunk of an arena by calling user code.
+///
+/// The elements are counted only when the slice is complete, or dropped (also during unwinding), and then the arena
+/// owns all elements written so far. If the user code allocates from the same arena meanwhile, the elements written
+/// so far are moved out of the way by `Chunks::settle`, and the rest are collected with them.
+pub(crate) struct Reserve<'a, C: Chunks> {
+  inner: &'a UnsafeCell<C>,
+  depth: usize,
+  // index in `Tails::moved` where the elements are moved to
+  base: usize,
+}
+
+impl<'a, C: Chunks> Reserve<'a, C> {
+  /// Reserve space for `additional` elements in the current chunk, retiring it if there is not enough room.
+  #[inline]
+  pub(crate) fn new(inner: &'a UnsafeCell<C>, additional: usize) -> Self {
+    let c = unsafe { &mut *inner.get() };
+    c.settle();
+    c.reserve(additional);
+    let base = c.tails().moved.len();
+    Reserve { inner, depth: c.take_tail(false), base }
+  }
+
+  /// Writes `t` after the elements written so far, moving them to a new chunk if the current one is full.
+  #[inline]
+  pub(crate) fn push(&mut self, t: Item<C>) {
+    let c = unsafe { &mut *self.inner.get() };
+    match c.tails().tail {
+      Some(tail) if tail.depth == self.depth => {
+        if c.len() + tail.pending == tail.end {
+          c.grow_tail();
+        }
+        let (p, len) = (c.as_mut_ptr(), c.len());
+        let tail = c.tails().tail.as_mut().unwrap();
+        unsafe { p.add(len + tail.pending).write(t); }
+        tail.pending += 1;
+      }
+      _ => c.tails().moved[self.base].push(t),
+    }
+  }
+
+  #[inline]
+  pub(crate) fn finish(mut self) -> &'a mut [Item<C>] {
+    let s = self.commit();
+    mem::forget(self);
+    s
+  }
+
+  /// Counts the elements written so far as allocated, after those allocated by user code meanwhile.
+  fn commit(&mut self) -> &'a mut [Item<C>] {
+    let c = unsafe { &mut *self.inner.get() };
+    unsafe {
+      let n = match c.release_tail(self.depth) {
+        Some(tail) => tail.pending,
+        None => {
+          let mut v = c.tails().moved.pop().unwrap();
+          debug_assert_eq!(c.tails().moved.len(), self.base);
+          let n = v.len();
+          c.reserve(n);
+          c.as_mut_ptr().add(c.len()).copy_from_nonoverlapping(v.as_ptr(), n);
+          v.set_len(0);
+          n
+        }
+      };
+      let len = c.len();
+      c.set_len(len + n);
+      slice::from_raw_parts_mut(c.as_mut_ptr().add(len), n)
+    }
+  }
+}
+
+impl<C: Chunks> Drop for Reserve<'_, C> {
+  fn drop(&mut self) { self.commit(); }
+}
+
+/// A slot at the end of the current chunk of an arena, reserved while user code constructs its value.
+///
+/// The slot is counted when the value is written, or as soon as the user code allocates from the same arena,
+/// which then allocates after it. The latter can't be undone if the user code panics, see `Chunks::forget_from`.
+pub(crate) struct ReserveSlot<'a, C: Chunks> {
+  inner: &'a UnsafeCell<C>,
+  depth: usize,
+  ptr: *mut Item<C>,
+  // the index of the slot, once it is counted
+  i: usize,
+}
+
+impl<'a, C: Chunks> ReserveSlot<'a, C> {
+  #[inline]
+  pub(crate) fn new(inner: &'a UnsafeCell<C>) -> Self {
+    let c = unsafe { &mut *inner.get() };
+    c.settle();
+    c.reserve(1);
+    let (i, ptr) = (c.retired_len() + c.len(), unsafe { c.as_mut_ptr().add(c.len()) });
+    ReserveSlot { inner, depth: c.take_tail(true), ptr, i }
+  }
+
+  /// Returns a pointer to the slot.
+  #[inline]
+  pub(crate) fn ptr(&self) -> *mut Item<C> { self.ptr }
+
+  /// Counts the slot, which must have been initialized, and returns a mutable reference to it.
+  #[inline]
+  pub(crate) unsafe fn finish(self) -> &'a mut Item<C> {
+    let c = &mut *self.inner.get();
+    if c.release_tail(self.depth).is_some() {
+      c.set_len(c.len() + 1);
+    }
+    let p = self.ptr;
+    mem::forget(self);
+    &mut *p
+  }
+}
+
+impl<C: Chunks> Drop for ReserveSlot<'_, C> {
+  fn drop(&mut self) {
+    let c = unsafe { &mut *self.inner.get() };
+    if c.release_tail(self.depth).is_none() {
+      c.forget_from(self.i);
+    }
+  }
+}
+
+type Item<C> = <C as Chunks>::Item;
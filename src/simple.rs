@@ -1,12 +1,14 @@
 #[cfg(feature = "no_std")]
-use alloc::{alloc::{alloc, dealloc, handle_alloc_error, Layout}, vec::Vec};
+use alloc::{alloc::{Global, Layout}, boxed::Box, vec::{self, Vec}};
 #[cfg(not(feature = "no_std"))]
-use std::{alloc::{alloc, dealloc, handle_alloc_error, Layout}, vec::Vec};
-use core::{mem, ptr::{self, Unique}, slice, isize, cell::UnsafeCell};
+use std::{alloc::{Global, Layout}, boxed::Box, vec::{self, Vec}};
+use core::{alloc::Allocator, iter::FromIterator, mem::{self, MaybeUninit}, ptr::{self, NonNull, Unique}, slice, cell::UnsafeCell, pin::Pin, fmt};
+use crate::{AllocError, arena::next_id, reserve::{Chunks, Tails, Reserve, ReserveSlot}};
 
 /// A simple arena of objects of type `T`.
 ///
-/// Allocating slices is not supported.
+/// It keeps less bookkeeping than `Arena`, and is intended for allocating objects one by one,
+/// though short slices can also be allocated by `alloc_slice` and `alloc_extend`.
 ///
 /// ## Example
 ///
@@ -19,24 +21,323 @@ use core::{mem, ptr::{self, Unique}, slice, isize, cell::UnsafeCell};
 /// let x = a.alloc(10);
 /// assert_eq!(*x, 10);
 /// ```
-pub struct SimpleArena<T>(UnsafeCell<Inner<T>>);
+///
+/// Its `Debug` output summarizes its state, and `debug_elements` prints the elements too:
+///
+/// ```
+/// use arena::SimpleArena;
+///
+/// let mut a = SimpleArena::new();
+/// for i in 0..5 { a.alloc(i); }
+/// assert_eq!(format!("{:?}", a), "SimpleArena { len: 5, chunk_count: 3, allocated_bytes: 28 }");
+/// assert_eq!(format!("{:?}", a.debug_elements(3)), "[0, 1, 2, ..]");
+/// assert_eq!(format!("{:?}", a.debug_elements(5)), "[0, 1, 2, 3, 4]");
+/// ```
+///
+/// Chunks are allocated by the global allocator, unless another allocator is given to `new_in` or `with_capacity_in`.
+pub struct SimpleArena<T, A: Allocator = Global>(UnsafeCell<Inner<T, A>>);
+
+struct Inner<T, A> {
+  cur: Chunk<T>,
+  rest: Vec<Chunk<T>>,
+  // `ends[i]` is the number of elements in `rest[..=i]`, used to locate an element by index
+  ends: Vec<usize>,
+  // the exact number of elements in `rest`, i.e. the last of `ends`, or 0
+  retired: usize,
+  // empty chunks kept by `clear`, the next one to use comes last
+  free: Vec<Chunk<T>>,
+  // the minimum capacity of a new chunk, at least 1
+  min_cap: usize,
+  // the size in bytes above which new chunks stop doubling
+  max_bytes: usize,
+  // the alignment of all chunks, at least `align_of::<T>()`
+  align: usize,
+  // the id recorded by checkpoints, or 0 if none has been recorded
+  id: usize,
+  // the live `Reserve`s and `ReserveSlot`s, the innermost of which sets `cur.end` to `cur.len` while writing past it
+  tails: Tails<T>,
+  // the allocator of all chunks
+  alloc: A,
+}
+
+/// The default maximum chunk size of a `SimpleArena`, 2 MiB.
+const MAX_CHUNK_BYTES: usize = 2 << 20;
+
+/// A chunk allocated by `SimpleArena`, whose first `len` slots are initialized,
+/// and whose slots from `end` are reserved by `alloc_uninit`.
+struct Chunk<T> {
+  ptr: Unique<T>,
+  len: usize,
+  end: usize,
+  cap: usize,
+}
+
+impl<T> Chunk<T> {
+  #[inline]
+  const fn new(ptr: Unique<T>, cap: usize) -> Self { Chunk { ptr, len: 0, end: cap, cap } }
+
+  /// A chunk without memory, which can't hold any element.
+  #[inline]
+  const fn empty() -> Self { Self::new(Unique::dangling(), 0) }
 
-struct Inner<T> {
-  cur: (Unique<T>, usize),
-  rest: Vec<Unique<T>>,
+  /// Returns the number of slots that are still free.
+  #[inline]
+  fn spare(&self) -> usize { self.end - self.len }
+
+  /// Returns `true` if no slot has been used, so the chunk can be freed or reused.
+  #[inline]
+  fn is_vacant(&self) -> bool { self.len == 0 && self.end == self.cap }
+
+  /// Drops the elements from `len` on.
+  #[inline]
+  unsafe fn truncate(&mut self, len: usize) {
+    let old = mem::replace(&mut self.len, len);
+    ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.ptr.as_ptr().add(len), old - len));
+  }
+
+  #[inline]
+  fn as_slice(&self) -> &[T] { unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) } }
+
+  #[inline]
+  fn as_mut_slice(&mut self) -> &mut [T] { unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) } }
+
+  /// Frees the chunk without dropping its elements. `alloc` and `align` must be those it was allocated with.
+  #[inline]
+  unsafe fn dealloc(&self, alloc: &impl Allocator, align: usize) {
+    if self.cap == 0 || mem::size_of::<T>() == 0 { return; }
+    // the layout was checked by `Inner::try_alloc_chunk`
+    let layout = Layout::from_size_align_unchecked(mem::size_of::<T>() * self.cap, align);
+    alloc.deallocate(NonNull::new_unchecked(self.ptr.as_ptr() as *mut u8), layout);
+  }
 }
 
 impl<T> SimpleArena<T> {
   /// Construct a new arena.
   ///
+  /// No memory is allocated until the first allocation, so an arena that is never used costs nothing.
+  /// This is a `const fn`, so it can initialize a `static`, e.g. behind a `Mutex`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  /// use std::alloc::{GlobalAlloc, Layout, System};
+  /// use std::sync::atomic::{AtomicUsize, Ordering};
+  ///
+  /// static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+  ///
+  /// struct Counter;
+  ///
+  /// unsafe impl GlobalAlloc for Counter {
+  ///   unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+  ///     ALLOCS.fetch_add(1, Ordering::Relaxed);
+  ///     System.alloc(layout)
+  ///   }
+  ///   unsafe fn dealloc(&self, p: *mut u8, layout: Layout) { System.dealloc(p, layout) }
+  /// }
+  ///
+  /// #[global_allocator]
+  /// static COUNTER: Counter = Counter;
+  ///
+  /// let before = ALLOCS.load(Ordering::Relaxed);
+  /// let a = SimpleArena::<u64>::new();
+  /// assert_eq!((a.chunk_count(), a.allocated_bytes()), (0, 0));
+  /// drop(a);
+  /// assert_eq!(ALLOCS.load(Ordering::Relaxed), before);
+  ///
+  /// let a = SimpleArena::new();
+  /// assert_eq!(a.alloc(1u64), &1);
+  /// assert_eq!(ALLOCS.load(Ordering::Relaxed), before + 1);
+  /// assert_eq!((a.chunk_count(), a.allocated_bytes()), (1, 8));
+  /// ```
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  /// use std::sync::Mutex;
+  ///
+  /// static A: Mutex<SimpleArena<u32>> = Mutex::new(SimpleArena::new());
+  /// const EMPTY: SimpleArena<u32> = SimpleArena::new();
+  ///
+  /// let mut a = A.lock().unwrap();
+  /// assert_eq!(a.alloc(1), &1);
+  /// assert_eq!(a.iter_mut().count(), 1);
+  /// assert!(EMPTY.is_empty());
+  /// ```
+  ///
+  /// Zero-sized types are supported, and never allocate any chunk memory:
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  /// use std::cell::Cell;
+  ///
+  /// thread_local!(static DROPS: Cell<usize> = Cell::new(0));
+  ///
+  /// struct Z;
+  /// impl Drop for Z {
+  ///   fn drop(&mut self) { DROPS.with(|d| d.set(d.get() + 1)); }
+  /// }
+  ///
+  /// let a = SimpleArena::new();
+  /// let x = a.alloc(Z) as *mut Z;
+  /// assert_eq!(x as usize % std::mem::align_of::<Z>(), 0);
+  /// for _ in 0..99 { a.alloc(Z); }
+  /// a.alloc_with(|| { a.alloc(Z); Z });
+  /// assert_eq!(a.len(), 102);
+  /// drop(a);
+  /// assert_eq!(DROPS.with(|d| d.get()), 102);
+  /// ```
+  #[inline]
+  pub const fn new() -> Self { Self::new_in(Global) }
+
+  /// Construct a new arena whose first chunk can hold `n` elements, or 1 element if `n` is 0.
+  ///
+  /// The first `n` calls to `alloc` will not allocate, and later chunks double from `n`.
+  /// For zero-sized types, `n` is ignored.
+  ///
   /// # Panics
   ///
-  /// Panic if T is a ZST or `mem::size_of::<T>() > isize::MAX`.
+  /// Panic if the size of the chunk overflows `isize`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let a = SimpleArena::with_capacity(100);
+  /// let first = a.alloc(0) as *mut i32;
+  /// for i in 1..100 {
+  ///   assert_eq!(a.alloc(i) as *mut i32, first.wrapping_add(i as usize));
+  /// }
+  /// // the second chunk holds 200 elements
+  /// let second = a.alloc(100) as *mut i32;
+  /// for i in 1..200 {
+  ///   assert_eq!(a.alloc(i) as *mut i32, second.wrapping_add(i as usize));
+  /// }
+  /// ```
   #[inline]
-  pub fn new() -> Self {
-    assert_ne!(mem::size_of::<T>(), 0);
-    assert!(mem::size_of::<T>() <= isize::MAX as usize);
-    unsafe { Self(UnsafeCell::new(Inner { cur: (Self::alloc_chunk(0), 0), rest: Vec::new() })) }
+  pub fn with_capacity(n: usize) -> Self { Self::with_capacity_in(n, Global) }
+
+  /// Construct a new arena whose chunks can all hold at least `n` elements.
+  ///
+  /// The first chunk holds `n` elements, and later chunks double from it, like `with_capacity(n)`.
+  /// The difference is that later chunks are never smaller than `n` either, even where they would otherwise be,
+  /// e.g. after `shrink_to_fit` shrinks the current chunk, or when `max_chunk_bytes` is smaller.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let a = SimpleArena::new();
+  /// for i in 0..1000u64 { a.alloc(i); }
+  /// assert_eq!(a.chunk_count(), 10);
+  /// let a = SimpleArena::with_chunk_capacity(256);
+  /// for i in 0..1000u64 { a.alloc(i); }
+  /// assert_eq!(a.chunk_count(), 3);
+  ///
+  /// let mut a = SimpleArena::with_chunk_capacity(16);
+  /// for i in 0..20u64 { a.alloc(i); }
+  /// // the second chunk of 32 elements is shrunk to 4 elements
+  /// a.shrink_to_fit();
+  /// // the next chunk holds 16 elements, instead of 8
+  /// a.alloc(20);
+  /// assert_eq!(a.allocated_bytes(), (16 + 4 + 16) * 8);
+  /// ```
+  #[inline]
+  pub fn with_chunk_capacity(n: usize) -> Self {
+    let mut a = Self::with_capacity(n);
+    a.0.get_mut().min_cap = n.max(1);
+    a
+  }
+
+  /// Construct a new arena whose chunks start at an address aligned to at least `align` bytes.
+  ///
+  /// This is useful to keep the chunks of different arenas on different cache lines, or to align them for SIMD.
+  /// Only the start of each chunk is aligned, since elements are still placed `size_of::<T>()` bytes apart.
+  ///
+  /// # Panics
+  ///
+  /// Panic if `align` is not a power of two.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let a = SimpleArena::with_chunk_align(64);
+  /// let mut count = 0;
+  /// for i in 0..1000 {
+  ///   let x = a.alloc([i as u8; 40]) as *mut _ as usize;
+  ///   if a.chunk_count() != count {
+  ///     count = a.chunk_count();
+  ///     assert_eq!(x % 64, 0);
+  ///   }
+  /// }
+  /// assert_eq!(count, 10);
+  /// ```
+  #[inline]
+  pub fn with_chunk_align(align: usize) -> Self {
+    assert!(align.is_power_of_two(), "chunk alignment is not a power of two");
+    let mut a = Self::new();
+    let inner = a.0.get_mut();
+    inner.align = inner.align.max(align);
+    a
+  }
+}
+
+impl<T, A: Allocator> SimpleArena<T, A> {
+  /// Construct a new arena whose chunks are allocated by `alloc`, like `new`.
+  ///
+  /// The allocator is stored in the arena, so every chunk is freed by the same allocator instance that allocated it.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// #![feature(allocator_api)]
+  /// use arena::SimpleArena;
+  /// use std::{alloc::{AllocError, Allocator, Global, Layout}, cell::Cell, ptr::NonNull};
+  ///
+  /// #[derive(Default)]
+  /// struct Counter { allocs: Cell<usize>, deallocs: Cell<usize> }
+  ///
+  /// unsafe impl Allocator for Counter {
+  ///   fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+  ///     self.allocs.set(self.allocs.get() + 1);
+  ///     Global.allocate(layout)
+  ///   }
+  ///   unsafe fn deallocate(&self, p: NonNull<u8>, layout: Layout) {
+  ///     self.deallocs.set(self.deallocs.get() + 1);
+  ///     Global.deallocate(p, layout)
+  ///   }
+  /// }
+  ///
+  /// let c = Counter::default();
+  /// let mut a = SimpleArena::new_in(&c);
+  /// for i in 0..100 { a.alloc(i); }
+  /// assert_eq!((c.allocs.get(), c.deallocs.get()), (7, 0));
+  /// a.reset();
+  /// assert_eq!((c.allocs.get(), c.deallocs.get()), (7, 6));
+  /// drop(a);
+  /// assert_eq!((c.allocs.get(), c.deallocs.get()), (7, 7));
+  ///
+  /// let a = SimpleArena::with_capacity_in(10, &c);
+  /// for i in 0..30 { a.alloc(i); }
+  /// assert_eq!(a.into_iter().sum::<i32>(), 435);
+  /// assert_eq!((c.allocs.get(), c.deallocs.get()), (9, 9));
+  /// ```
+  #[inline]
+  pub const fn new_in(alloc: A) -> Self {
+    Self(UnsafeCell::new(Inner { cur: Chunk::empty(), rest: Vec::new(), ends: Vec::new(), retired: 0, free: Vec::new(), min_cap: 1, max_bytes: MAX_CHUNK_BYTES, align: mem::align_of::<T>(), id: 0, tails: Tails::new(), alloc }))
+  }
+
+  /// Construct a new arena whose chunks are allocated by `alloc`, like `with_capacity`.
+  #[inline]
+  pub fn with_capacity_in(n: usize, alloc: A) -> Self {
+    let cap = if mem::size_of::<T>() == 0 { usize::MAX } else { n.max(1) };
+    let mut a = Self::new_in(alloc);
+    let inner = a.0.get_mut();
+    inner.cur = match unsafe { inner.try_alloc_chunk(cap) } { Ok(p) => Chunk::new(p, cap), Err(e) => e.raise() };
+    a
   }
 
   /// Allocates a value in the arena, and returns a mutable reference to it.
@@ -45,33 +346,1383 @@ impl<T> SimpleArena<T> {
   /// otherwise it is impossible for arena to allocate more than one object.
   #[inline]
   pub fn alloc(&self, t: T) -> &mut T {
+    match self.try_alloc(t) { Ok(x) => x, Err(e) => e.raise() }
+  }
+
+  /// Allocates a value in the arena, and returns a mutable reference to it,
+  /// or an error containing the value if a new chunk can't be allocated.
+  ///
+  /// If the allocator fails to provide a new chunk, the error carries the layout that was requested, and the arena
+  /// can still be used afterwards, e.g. once memory is freed.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  /// use std::alloc::{GlobalAlloc, Layout, System};
+  /// use std::sync::atomic::{AtomicBool, Ordering};
+  ///
+  /// static FAIL: AtomicBool = AtomicBool::new(false);
+  ///
+  /// struct Shim;
+  ///
+  /// unsafe impl GlobalAlloc for Shim {
+  ///   unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+  ///     if FAIL.load(Ordering::Relaxed) { std::ptr::null_mut() } else { System.alloc(layout) }
+  ///   }
+  ///   unsafe fn dealloc(&self, p: *mut u8, layout: Layout) { System.dealloc(p, layout) }
+  /// }
+  ///
+  /// #[global_allocator]
+  /// static SHIM: Shim = Shim;
+  ///
+  /// let a = SimpleArena::with_capacity(1);
+  /// for i in 0..3 { assert_eq!(a.try_alloc(i).ok().copied(), Some(i)); }
+  /// FAIL.store(true, Ordering::Relaxed);
+  /// let e = a.try_alloc(3).unwrap_err();
+  /// FAIL.store(false, Ordering::Relaxed);
+  /// assert_eq!(e.layout(), Some(Layout::new::<[i32; 4]>()));
+  /// assert_eq!(e.into_inner(), 3);
+  /// assert_eq!(a.try_alloc(4).ok(), Some(&mut 4));
+  /// assert_eq!(a.into_vec(), [0, 1, 2, 4]);
+  /// ```
+  #[inline]
+  pub fn try_alloc(&self, t: T) -> Result<&mut T, AllocError<T>> {
+    self.try_alloc_ptr(t).map(|p| unsafe { &mut *p.as_ptr() })
+  }
+
+  /// Allocates a value in the arena, and returns a pointer to it, without creating a reference to it.
+  ///
+  /// The pointer is valid for reads and writes until the arena is dropped, or any method taking `&mut self` is called,
+  /// since methods like `shrink_to_fit`, `take_all` or `reset` may move or drop the elements. This makes it suitable
+  /// to be handed to foreign code. As usual, it must not be used while a reference to the same element is alive,
+  /// e.g. one created from the pointer itself.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// // stands for a C function storing the pointer and writing through it later
+  /// unsafe fn c_side(p: *mut i32, v: i32) { *p = v; }
+  ///
+  /// let a = SimpleArena::new();
+  /// let p = a.alloc_raw(1);
+  /// let q = a.alloc_raw(2);
+  /// for i in 3..10 { a.alloc(i); }
+  /// unsafe {
+  ///   c_side(p.as_ptr(), 10);
+  ///   c_side(q.as_ptr(), 20);
+  ///   assert_eq!((*p.as_ref(), *q.as_ref()), (10, 20));
+  /// }
+  /// assert_eq!(a.into_vec(), [10, 20, 3, 4, 5, 6, 7, 8, 9]);
+  /// ```
+  #[inline]
+  pub fn alloc_raw(&self, t: T) -> NonNull<T> {
+    match self.try_alloc_ptr(t) { Ok(p) => p, Err(e) => e.raise() }
+  }
+
+  /// Allocates a value in the arena, and returns a pointer to it, see `try_alloc` and `alloc_raw`.
+  #[inline]
+  fn try_alloc_ptr(&self, t: T) -> Result<NonNull<T>, AllocError<T>> {
     unsafe {
-      let Inner { cur, rest } = &mut *self.0.get();
-      if cur.1 == 1 << rest.len() {
-        let old = mem::replace(&mut cur.0, Self::alloc_chunk(rest.len() + 1));
-        rest.push(old);
-        cur.1 = 0;
+      let inner = &mut *self.0.get();
+      if inner.cur.spare() == 0 {
+        if let Err(e) = inner.try_grow(1) { return Err(e.with(t)); }
       }
-      let p = cur.0.as_ptr().add(cur.1);
+      let cur = &mut inner.cur;
+      let p = cur.ptr.as_ptr().add(cur.len);
       p.write(t);
-      cur.1 += 1;
-      &mut *p
+      cur.len += 1;
+      Ok(NonNull::new_unchecked(p))
     }
   }
 
+  /// Reserves a slot in the arena, and returns a mutable reference to it, uninitialized.
+  ///
+  /// Just like `MaybeUninit<T>` itself, the arena never knows whether the slot has been initialized,
+  /// so it never drops its content, and safe code can't make it drop uninitialized memory. A value can be
+  /// dropped in place by the caller, or constructed with `alloc_with` instead. The slot is not counted by `len`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  /// use std::{mem::MaybeUninit, rc::Rc};
+  ///
+  /// let a = SimpleArena::new();
+  /// let slot: &mut MaybeUninit<[u8; 4]> = a.alloc_uninit();
+  /// let x = slot.write(*b"abcd");
+  /// let y = a.alloc(*b"efgh");
+  /// assert_eq!((&*x, &*y), (b"abcd", b"efgh"));
+  /// assert_eq!(a.len(), 1);
+  ///
+  /// let r = Rc::new(());
+  /// let mut a = SimpleArena::new();
+  /// a.alloc_uninit(); // abandoned
+  /// a.alloc_uninit().write(r.clone()); // leaked
+  /// a.alloc(r.clone());
+  /// assert_eq!(a.iter_mut().count(), 1);
+  /// drop(a);
+  /// assert_eq!(Rc::strong_count(&r), 2);
+  /// # // release the leaked clone, so that Miri doesn't report it
+  /// # unsafe { Rc::decrement_strong_count(Rc::as_ptr(&r)); }
+  /// ```
+  #[inline]
+  pub fn alloc_uninit(&self) -> &mut MaybeUninit<T> {
+    unsafe {
+      let inner = &mut *self.0.get();
+      if inner.cur.spare() == 0 {
+        inner.grow(1);
+      }
+      inner.cur.end -= 1;
+      &mut *(inner.cur.ptr.as_ptr().add(inner.cur.end) as *mut MaybeUninit<T>)
+    }
+  }
+
+  /// Allocates a slice in the arena, and returns a mutable reference to it.
+  ///
+  /// The elements are moved into one chunk, and the buffer of `v` is freed. If they don't fit in the current chunk,
+  /// it is retired early, leaving its free slots unused, and a new one that can hold them is allocated.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let a = SimpleArena::with_capacity(4);
+  /// let x = a.alloc(0) as *mut i32;
+  /// assert_eq!(a.alloc_slice(vec![1, 2]).as_mut_ptr(), x.wrapping_add(1));
+  /// assert_eq!(a.alloc_slice(vec![]), &[]);
+  /// // doesn't fit in the first chunk
+  /// let y = a.alloc_slice(vec![3, 4, 5]);
+  /// assert_eq!(y, &[3, 4, 5]);
+  /// assert_eq!(a.wasted_bytes(), 4);
+  /// assert_eq!(a.alloc(6) as *mut i32, y.as_mut_ptr().wrapping_add(3));
+  /// assert_eq!(a.into_vec(), [0, 1, 2, 3, 4, 5, 6]);
+  /// ```
+  #[inline]
+  pub fn alloc_slice(&self, mut v: Vec<T>) -> &mut [T] {
+    unsafe {
+      let inner = &mut *self.0.get();
+      let n = v.len();
+      if inner.cur.spare() < n {
+        inner.grow(n);
+      }
+      let cur = &mut inner.cur;
+      let p = cur.ptr.as_ptr().add(cur.len);
+      ptr::copy_nonoverlapping(v.as_ptr(), p, n);
+      v.set_len(0);
+      cur.len += n;
+      slice::from_raw_parts_mut(p, n)
+    }
+  }
+
+  /// Allocates all items of `iter` in the arena as a contiguous slice, and returns a mutable reference to it.
+  ///
+  /// The items are collected into a temporary `Vec` first and then moved by `alloc_slice`,
+  /// so `iter` may itself allocate from the arena. If it panics, the items yielded so far are dropped.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let a = SimpleArena::new();
+  /// let x = a.alloc_extend((0..8).map(|i| i * 2));
+  /// assert_eq!(x, &[0, 2, 4, 6, 8, 10, 12, 14]);
+  /// let y = a.alloc_extend((0..3).map(|i| *a.alloc(i) + 10));
+  /// assert_eq!(y, &[10, 11, 12]);
+  /// assert_eq!(a.alloc_extend(None), &[]);
+  /// assert_eq!(a.len(), 14);
+  /// ```
+  #[inline]
+  pub fn alloc_extend(&self, iter: impl IntoIterator<Item = T>) -> &mut [T] { self.alloc_slice(iter.into_iter().collect()) }
+
+  /// Returns an iterator that allocates each item of `iter` in the arena one by one, like `alloc`,
+  /// and yields a mutable reference to it.
+  ///
+  /// Unlike `alloc_extend`, the items are not required to be contiguous, so nothing is collected. The returned iterator
+  /// is lazy: an item is taken from `iter` and allocated only when the iterator is advanced, so dropping it early
+  /// simply stops allocating, and leaves the rest of `iter` untouched.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let a = SimpleArena::new();
+  /// let refs: Vec<&mut i32> = a.alloc_each(0..100).collect();
+  /// assert!(refs.into_iter().enumerate().all(|(i, x)| *x == i as i32));
+  /// let mut src = 100..200;
+  /// let mut it = a.alloc_each(&mut src);
+  /// *it.next().unwrap() += 1000;
+  /// drop(it);
+  /// assert_eq!((a.len(), src.next()), (101, Some(101)));
+  /// a.alloc_each(None).for_each(drop);
+  /// assert_eq!(a.into_vec()[100], 1100);
+  /// ```
+  #[inline]
+  pub fn alloc_each<'a>(&'a self, iter: impl IntoIterator<Item = T> + 'a) -> impl Iterator<Item = &'a mut T> + 'a {
+    iter.into_iter().map(move |t| self.alloc(t))
+  }
+
+  /// Allocates `n` clones of `value` in the arena as a contiguous slice, and returns a mutable reference to it.
+  ///
+  /// `value` itself is moved into the last slot. If the clones don't fit in the current chunk, it is retired early
+  /// like `alloc_slice`. If `clone` panics, the clones made so far stay in the arena. If `n` is 0, `value` is dropped.
+  ///
+  /// `clone` may itself allocate from the arena. The clones made so far are then moved out of the way,
+  /// and the slice is allocated when all clones are made, so it comes after the values allocated by `clone`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  /// use std::{cell::Cell, panic::{self, AssertUnwindSafe}, rc::Rc};
+  ///
+  /// let a = SimpleArena::new();
+  /// assert_eq!(a.alloc_n(3, 7), &[7, 7, 7]);
+  /// assert_eq!(a.alloc_n(0, 8), &[]);
+  /// assert_eq!(a.len(), 3);
+  ///
+  /// thread_local!(static CLONES: Cell<usize> = Cell::new(0));
+  ///
+  /// struct P(Rc<()>);
+  /// impl Clone for P {
+  ///   fn clone(&self) -> Self {
+  ///     CLONES.with(|c| { c.set(c.get() + 1); if c.get() == 5 { panic!() } });
+  ///     P(self.0.clone())
+  ///   }
+  /// }
+  ///
+  /// let r = Rc::new(());
+  /// let a = SimpleArena::new();
+  /// let res = panic::catch_unwind(AssertUnwindSafe(|| { a.alloc_n(10, P(r.clone())); }));
+  /// assert!(res.is_err());
+  /// assert_eq!((a.len(), Rc::strong_count(&r)), (4, 5));
+  /// drop(a);
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  ///
+  /// // `clone` allocating from the same arena
+  /// struct C(*const SimpleArena<C>, u32);
+  /// impl Clone for C {
+  ///   fn clone(&self) -> Self {
+  ///     unsafe { (*self.0).alloc(C(self.0, 0)); }
+  ///     C(self.0, self.1)
+  ///   }
+  /// }
+  ///
+  /// let mut a = SimpleArena::with_capacity(16);
+  /// let s = a.alloc_n(3, C(&a, 1)).as_ptr();
+  /// assert_eq!((a.len(), a.chunk_count()), (5, 1));
+  /// assert!(a.iter_mut().map(|c| c.1).eq([0, 0, 1, 1, 1]));
+  /// assert_eq!(a.get(2).unwrap() as *const C, s);
+  /// ```
+  pub fn alloc_n(&self, n: usize, value: T) -> &mut [T] where T: Clone {
+    if n == 0 { return &mut []; }
+    let mut r = Reserve::new(&self.0, n);
+    for _ in 1..n { r.push(value.clone()); }
+    r.push(value);
+    r.finish()
+  }
+
+  /// Allocates a value in the arena, and returns a pinned mutable reference to it.
+  ///
+  /// Elements of a `SimpleArena` are never moved to make room for others, and they are dropped in place when the arena
+  /// is dropped, cleared or reset, so the pinning guarantee holds, even for `!Unpin` types such as
+  /// self-referential futures or intrusive list nodes. Methods that would let a pinned element be moved,
+  /// like `iter_mut`, `into_vec` or `shrink_to_fit`, require `T: Unpin`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  /// use std::{marker::PhantomPinned, pin::Pin};
+  ///
+  /// // a self-referential struct
+  /// struct Node { val: i32, this: *const Node, _pin: PhantomPinned }
+  ///
+  /// let mut a = SimpleArena::new();
+  /// let mut x: Pin<&mut Node> = a.alloc_pin(Node { val: 1, this: std::ptr::null(), _pin: PhantomPinned });
+  /// let p = &*x as *const Node;
+  /// unsafe { x.as_mut().get_unchecked_mut().this = p; }
+  /// assert_eq!(unsafe { (*x.this).val }, 1);
+  /// let mut n = 0;
+  /// a.for_each(|x| { assert_eq!(x.this, x as *const Node); n += 1; });
+  /// assert_eq!(n, 1);
+  /// a.clear();
+  /// ```
+  ///
+  /// Pinned elements can't be obtained as a movable `&mut T` afterwards:
+  ///
+  /// ```compile_fail
+  /// use arena::SimpleArena;
+  /// use std::marker::PhantomPinned;
+  ///
+  /// let mut a = SimpleArena::new();
+  /// a.alloc_pin(PhantomPinned);
+  /// let x: &mut PhantomPinned = a.last_mut().unwrap();
+  /// ```
+  #[inline]
+  pub fn alloc_pin(&self, t: T) -> Pin<&mut T> {
+    unsafe { Pin::new_unchecked(self.alloc(t)) }
+  }
+
+  /// Allocates the value returned by `f` in the arena, and returns a mutable reference to it.
+  ///
+  /// The slot is reserved before calling `f`, so that the value can be constructed directly in it,
+  /// instead of being moved from the stack. It is counted only after `f` returns, so if `f` panics, nothing is allocated.
+  ///
+  /// `f` may itself allocate from the arena. The slot is then counted first, so it comes before the values allocated
+  /// by `f` in allocation order, and they are allocated after it in the same chunk. If `f` panics after allocating,
+  /// these values are leaked: they are no longer counted, and are never dropped, but their slots are never reused.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  /// use std::panic::{self, AssertUnwindSafe};
+  ///
+  /// let a = SimpleArena::new();
+  /// let x = a.alloc_with(|| [1u64; 1024]);
+  /// let y = a.alloc_with(|| { a.alloc([2; 1024]); [3; 1024] });
+  /// assert_eq!((x[0], y[0], a.len()), (1, 3, 3));
+  /// let r = panic::catch_unwind(AssertUnwindSafe(|| { a.alloc_with(|| -> [u64; 1024] { panic!() }); }));
+  /// assert!(r.is_err());
+  /// let r = panic::catch_unwind(AssertUnwindSafe(|| { a.alloc_with(|| -> [u64; 1024] { a.alloc([4; 1024]); panic!() }); }));
+  /// assert!(r.is_err());
+  /// assert_eq!(a.len(), 3);
+  /// for _ in 0..10 { a.alloc([5; 1024]); }
+  /// assert_eq!(a.len(), 13);
+  /// ```
+  ///
+  /// Values allocated by `f` fill the current chunk like any other:
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let mut a = SimpleArena::<u32>::with_capacity(1024);
+  /// for _ in 0..1000 { a.alloc_with(|| { a.alloc(1); 2 }); }
+  /// assert_eq!((a.chunk_count(), a.allocated_bytes()), (2, (1024 + 2048) * 4));
+  /// assert!(a.iter_mut().map(|x| *x).eq([2, 1].iter().copied().cycle().take(2000)));
+  /// ```
+  #[inline]
+  pub fn alloc_with(&self, f: impl FnOnce() -> T) -> &mut T { self.alloc_cyclic(|_| f()) }
+
+  /// Allocates the value returned by `f` in the arena, where `f` is given the address the value will live at,
+  /// and returns a mutable reference to it.
+  ///
+  /// This allows a value to contain pointers to itself, e.g. the head of an intrusive circular list.
+  /// The pointer must not be dereferenced until `alloc_cyclic` returns, since the slot is uninitialized while `f` runs;
+  /// after that, it is valid as long as the returned reference would be, but like any raw pointer the returned
+  /// reference was derived from, using it ends the use of that reference. Like `alloc_with`, if `f` panics,
+  /// nothing is allocated, and `f` may itself allocate from the arena. The address doesn't change when `f` allocates.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  /// use std::ptr::NonNull;
+  ///
+  /// struct Node { val: i32, prev: NonNull<Node>, next: NonNull<Node> }
+  ///
+  /// let a = SimpleArena::new();
+  /// let head = a.alloc_cyclic(|p| Node { val: 1, prev: p, next: p });
+  /// let next = head.next;
+  /// assert_eq!((head.prev, head as *mut Node), (next, next.as_ptr()));
+  /// assert_eq!(unsafe { next.as_ref().prev.as_ref().val }, 1);
+  /// ```
+  #[inline]
+  pub fn alloc_cyclic(&self, f: impl FnOnce(NonNull<T>) -> T) -> &mut T {
+    let r = ReserveSlot::new(&self.0);
+    unsafe {
+      r.ptr().write(f(NonNull::new_unchecked(r.ptr())));
+      r.finish()
+    }
+  }
+
+  /// Reserves capacity for at least `additional` more elements in the current chunk.
+  ///
+  /// After this, the next `additional` calls to `alloc` will not allocate. If there is not enough room,
+  /// the current chunk is retired early, leaving its free slots unused, and a new one is allocated,
+  /// at least twice as large as it, as if growing normally.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let a = SimpleArena::with_capacity(4);
+  /// a.alloc(0);
+  /// a.reserve(3);
+  /// let x = a.alloc(1) as *mut i32;
+  /// assert_eq!(a.alloc(2) as *mut i32, x.wrapping_add(1));
+  /// a.reserve(100);
+  /// let y = a.alloc(3) as *mut i32;
+  /// for i in 1..100 { assert_eq!(a.alloc(4) as *mut i32, y.wrapping_add(i)); }
+  /// assert_eq!(a.into_vec().len(), 103);
+  /// ```
+  #[inline]
+  pub fn reserve(&self, additional: usize) {
+    if let Err(e) = self.try_reserve(additional) { e.raise() }
+  }
+
+  /// Tries to reserve capacity for at least `additional` more elements in the current chunk, like `reserve`.
+  ///
+  /// The request fails if the new chunk would be too large for a `Layout`, or if the allocator can't provide it.
+  /// No chunk is retired then, so the elements allocated next still go to the current chunk.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let a = SimpleArena::<u64>::new();
+  /// a.alloc(0);
+  /// assert!(a.try_reserve(usize::MAX).is_err());
+  /// a.try_reserve(10).unwrap();
+  /// assert_eq!(a.len(), 1);
+  /// ```
+  #[inline]
+  pub fn try_reserve(&self, additional: usize) -> Result<(), AllocError> {
+    let inner = unsafe { &mut *self.0.get() };
+    if inner.cur.spare() < additional {
+      unsafe { inner.try_grow(additional)?; }
+    }
+    Ok(())
+  }
+
+  /// Allocates `T::default()` in the arena, and returns a mutable reference to it.
+  ///
+  /// Like `alloc_with`, the value is constructed in place instead of being moved from the stack.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let a = SimpleArena::<[u64; 32]>::new();
+  /// assert_eq!(a.alloc_default(), &[0; 32]);
+  /// ```
+  #[inline]
+  pub fn alloc_default(&self) -> &mut T where T: Default { self.alloc_with(T::default) }
+
+  /// Allocates a value in the arena, and returns its index together with a mutable reference to it.
+  ///
+  /// The index is the position of the value in allocation order, to be used with `get` and `get_mut`. It doesn't
+  /// depend on the layout of chunks, and stays valid until the arena is cleared or rolled back before it.
+  /// Storing indices instead of references keeps lifetimes out of data structures, and an index stored as `u32`
+  /// takes 4 bytes, half the size of a reference on 64-bit targets.
+  ///
+  /// ## Example
+  ///
+  /// A graph whose edges refer to their nodes by index:
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// struct Node { name: char, edges: Vec<u32> }
+  ///
+  /// let mut a = SimpleArena::new();
+  /// let idx: Vec<u32> = "abcd".chars().map(|name| a.alloc_idx(Node { name, edges: Vec::new() }).0 as u32).collect();
+  /// for (&from, &to) in idx.iter().zip(&idx[1..]) {
+  ///   a.get_mut(from as usize).unwrap().edges.push(to);
+  ///   a.get_mut(to as usize).unwrap().edges.push(from);
+  /// }
+  /// let edges = a.get(idx[1] as usize).unwrap().edges.clone();
+  /// let names: Vec<char> = edges.iter().map(|&i| a.get(i as usize).unwrap().name).collect();
+  /// assert_eq!(names, ['a', 'c']);
+  /// ```
+  ///
+  /// Values allocated while `alloc_extend` or `alloc_with` runs user code get the right indices:
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let mut a = SimpleArena::new();
+  /// let mut idx = Vec::new();
+  /// a.alloc(0);
+  /// a.alloc_extend((0..2).map(|i| { idx.push(a.alloc_idx(i + 1).0); i + 100 }));
+  /// a.alloc_with(|| { idx.push(a.alloc_idx(3).0); 200 });
+  /// assert_eq!(idx, [1, 2, 6]);
+  /// assert!(idx.iter().map(|&i| *a.get(i).unwrap()).eq([1, 2, 3]));
+  /// assert_eq!(a.into_vec(), [0, 1, 2, 100, 101, 200, 3]);
+  /// ```
+  #[inline]
+  pub fn alloc_idx(&self, t: T) -> (usize, &mut T) {
+    let x = self.alloc(t);
+    (self.len() - 1, x)
+  }
+
+  /// Returns the number of elements allocated in the arena.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let a = SimpleArena::new();
+  /// assert!(a.is_empty());
+  /// for i in 1..=16 {
+  ///   a.alloc(i);
+  ///   assert_eq!(a.len(), i);
+  /// }
+  /// let a = SimpleArena::with_capacity(3);
+  /// for i in 1..10 { a.alloc(i); }
+  /// assert_eq!(a.len(), 9);
+  /// ```
+  #[inline]
+  pub fn len(&self) -> usize {
+    let inner = unsafe { &*self.0.get() };
+    inner.retired + inner.cur.len
+  }
+
+  /// Returns `true` if no element has been allocated in the arena.
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+  /// Returns the total size in bytes of all chunks, including those kept by `clear` for reuse.
+  ///
+  /// The memory used to keep track of the chunks themselves is reported by `overhead_bytes` instead.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let a = SimpleArena::new();
+  /// for i in 0..10000u64 { a.alloc(i); }
+  /// println!("chunks:    {}", a.chunk_count());
+  /// println!("allocated: {} bytes", a.allocated_bytes());
+  /// println!("used:      {} bytes", a.used_bytes());
+  /// println!("wasted:    {} bytes", a.wasted_bytes());
+  /// println!("overhead:  {} bytes", a.overhead_bytes());
+  /// // chunks of 1, 2, 4, ..., 8192 elements
+  /// assert_eq!((a.chunk_count(), a.allocated_bytes(), a.used_bytes()), (14, 16383 * 8, 10000 * 8));
+  /// // the current chunk is partially filled, but its free slots are not wasted
+  /// assert_eq!(a.wasted_bytes(), 0);
+  /// a.reserve(10000);
+  /// assert_eq!(a.wasted_bytes(), 6383 * 8);
+  /// ```
+  pub fn allocated_bytes(&self) -> usize {
+    let Inner { cur, rest, free, .. } = unsafe { &*self.0.get() };
+    rest.iter().chain(Some(cur)).chain(free).map(|c| c.cap * mem::size_of::<T>()).sum()
+  }
+
+  /// Returns the total size in bytes of all allocated elements, i.e. `len() * size_of::<T>()`.
+  #[inline]
+  pub fn used_bytes(&self) -> usize { self.len() * mem::size_of::<T>() }
+
+  /// Returns the total size in bytes of the free slots in retired chunks, which will never be used.
+  ///
+  /// Chunks are retired with free slots when there is not enough room for `reserve`, `alloc_slice`, `alloc_extend`
+  /// or `alloc_n`, and when `extend` reserves room. The slots of values leaked when `f` panics in `alloc_with`
+  /// or `alloc_cyclic` are never used either, and are counted once their chunk is retired.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let a = SimpleArena::<u64>::with_capacity(4);
+  /// a.alloc(0);
+  /// // doesn't fit in the 3 free slots of the first chunk, and takes 4 slots of a chunk of 8 elements
+  /// a.alloc_slice(vec![1, 2, 3, 4]);
+  /// assert_eq!(a.wasted_bytes(), 3 * 8);
+  /// a.alloc_n(10, 5);
+  /// assert_eq!(a.wasted_bytes(), (3 + 4) * 8);
+  /// a.alloc_slice(vec![6; 6]);
+  /// assert_eq!((a.wasted_bytes(), a.allocated_bytes()), ((3 + 4) * 8, (4 + 8 + 16) * 8));
+  /// ```
+  pub fn wasted_bytes(&self) -> usize {
+    let Inner { rest, .. } = unsafe { &*self.0.get() };
+    rest.iter().map(|c| c.spare() * mem::size_of::<T>()).sum()
+  }
+
+  /// Returns the size in bytes of the memory used to keep track of retired chunks and chunks kept by `clear`.
+  #[inline]
+  pub fn overhead_bytes(&self) -> usize {
+    let Inner { rest, ends, free, .. } = unsafe { &*self.0.get() };
+    (rest.capacity() + free.capacity()) * mem::size_of::<Chunk<T>>() + ends.capacity() * mem::size_of::<usize>()
+  }
+
+  /// Returns the maximum chunk size in bytes, 2 MiB by default.
+  ///
+  /// Once chunks reach this size, new chunks stay at it instead of doubling, so that the free slots of
+  /// the current chunk are bounded. Chunks can still be larger to hold a slice, or to honor `reserve`,
+  /// `with_capacity` or `with_chunk_capacity`.
+  #[inline]
+  pub fn max_chunk_bytes(&self) -> usize { unsafe { (*self.0.get()).max_bytes } }
+
+  /// Sets the maximum chunk size in bytes, see `max_chunk_bytes`.
+  ///
+  /// It only affects chunks allocated later.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let mut a = SimpleArena::new();
+  /// assert_eq!(a.max_chunk_bytes(), 2 << 20);
+  /// a.set_max_chunk_bytes(1024);
+  /// for i in 0..2000u64 {
+  ///   a.alloc(i);
+  ///   if i % 100 == 0 { assert!(a.allocated_bytes() - a.used_bytes() < 1024); }
+  /// }
+  /// // chunks of 1, 2, 4, ..., 128 elements, and then 128 elements each
+  /// assert_eq!(a.chunk_count(), 8 + (2000 - 255 + 127) / 128);
+  /// ```
+  #[inline]
+  pub fn set_max_chunk_bytes(&mut self, bytes: usize) { self.0.get_mut().max_bytes = bytes; }
+
+  /// Returns the number of chunks, including the current one (if any) and those kept by `clear`.
   #[inline]
-  unsafe fn alloc_chunk(level: usize) -> Unique<T> {
-    let (size, align) = (mem::size_of::<T>(), mem::align_of::<T>());
-    // `size << level` never overflows because:
-    // 1. it can be either be `mem::size_of::<T>()`, or 2 * previous cap
-    // 2. - for 64-bit platform, allocation will fail before previous cap reaches usize::MAX / 2
-    //    - for 32-bit or 16-bit platform, previous cap <= isize::MAX as usize < usize::MAX / 2
-    let cap = size << level;
-    // this assertion is a no-op for 64-bit platform
-    assert!(!(mem::size_of::<usize>() < 8 && cap > isize::MAX as usize), "capacity overflow");
-    let layout = Layout::from_size_align_unchecked(cap, align);
-    let p = alloc(layout);
-    if p.is_null() { handle_alloc_error(layout) } else { Unique::new_unchecked(p as _) }
+  pub fn chunk_count(&self) -> usize {
+    let Inner { cur, rest, free, .. } = unsafe { &*self.0.get() };
+    rest.len() + (cur.cap != 0) as usize + free.len()
+  }
+
+  /// Returns an iterator over the statistics of each chunk, see `ChunkStat`.
+  ///
+  /// Retired chunks come first in allocation order, then the current chunk (if any), and then the chunks kept by `clear`.
+  /// This takes `&mut self`, so that no chunk can be added while iterating.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::simple::{SimpleArena, ChunkStat};
+  ///
+  /// let stat = |capacity, len| ChunkStat { capacity, len, bytes: capacity * 8 };
+  /// let mut a = SimpleArena::<u64>::new();
+  /// assert_eq!(a.chunk_stats().count(), 0);
+  /// for i in 0..3 { a.alloc(i); }
+  /// a.reserve(10);
+  /// a.alloc(3);
+  /// assert!(a.chunk_stats().eq([stat(1, 1), stat(2, 2), stat(10, 1)]));
+  /// // the previous chunk is retired with free slots, which are wasted
+  /// a.reserve(20);
+  /// assert!(a.chunk_stats().eq([stat(1, 1), stat(2, 2), stat(10, 1), stat(20, 0)]));
+  /// assert_eq!(a.wasted_bytes(), 9 * 8);
+  /// let sum = a.chunk_stats().fold((0, 0), |(n, b), s| (n + s.len, b + s.bytes));
+  /// assert_eq!(sum, (a.len(), a.allocated_bytes()));
+  /// a.clear();
+  /// assert!(a.chunk_stats().eq([stat(1, 0), stat(20, 0), stat(10, 0), stat(2, 0)]));
+  /// ```
+  pub fn chunk_stats(&mut self) -> impl Iterator<Item = ChunkStat> + '_ {
+    let Inner { cur, rest, free, .. } = self.0.get_mut();
+    let cur = if cur.cap != 0 { Some(&*cur) } else { None };
+    rest.iter().chain(cur).chain(free.iter()).map(|c| ChunkStat { capacity: c.cap, len: c.len, bytes: c.cap * mem::size_of::<T>() })
+  }
+
+  /// Returns an iterator over mutable references to all elements, in allocation order.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let mut a = SimpleArena::new();
+  /// assert_eq!(a.iter_mut().next(), None);
+  /// // the current chunk is full after this, and the next `alloc` will retire it
+  /// for i in 0..7 { a.alloc(i); }
+  /// assert!(a.iter_mut().map(|x| *x).eq(0..7));
+  /// a.alloc(7);
+  /// for x in a.iter_mut() { *x *= 2; }
+  /// assert_eq!(a.iter_mut().len(), 8);
+  /// assert!(a.iter_mut().map(|x| *x).eq((0..8).map(|x| x * 2)));
+  /// assert!(a.iter_mut().rev().map(|x| *x).eq((0..8).rev().map(|x| x * 2)));
+  /// ```
+  #[inline]
+  pub fn iter_mut(&mut self) -> IterMut<'_, T> where T: Unpin {
+    let len = self.len();
+    let Inner { cur, rest, .. } = self.0.get_mut();
+    IterMut { chunks: rest.iter_mut(), front: [].iter_mut(), back: cur.as_mut_slice().iter_mut(), len }
+  }
+
+  /// Returns an iterator over the elements of each chunk as mutable slices, in allocation order.
+  ///
+  /// Retired chunks come first, and the initialized part of the current chunk comes last, which is empty right after
+  /// the current chunk is replaced, or if nothing is allocated yet. Slots reserved by `alloc_uninit` are never included,
+  /// so the lengths of all slices add up to `len()`. Chunks kept by `clear` hold no elements and are skipped.
+  ///
+  /// ## Example
+  ///
+  /// Sum each chunk on its own thread:
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  /// use std::thread;
+  ///
+  /// let mut a = SimpleArena::new();
+  /// assert!(a.chunks_mut().eq([&mut [][..]]));
+  /// for i in 0..7 { a.alloc(i); }
+  /// // the first three chunks are full, and the new current chunk is empty
+  /// a.reserve(1);
+  /// assert!(a.chunks_mut().map(|c| c.len()).eq([1, 2, 4, 0]));
+  /// a.alloc(7);
+  /// let sums: Vec<i32> = thread::scope(|s| {
+  ///   let threads: Vec<_> = a.chunks_mut().map(|c| s.spawn(move || { c.reverse(); c.iter().sum() })).collect();
+  ///   threads.into_iter().map(|t| t.join().unwrap()).collect()
+  /// });
+  /// assert_eq!(sums, [0, 3, 18, 7]);
+  /// assert_eq!(a.chunks_mut().map(|c| c.len()).sum::<usize>(), a.len());
+  /// assert_eq!(a.into_vec(), [0, 2, 1, 6, 5, 4, 3, 7]);
+  /// ```
+  pub fn chunks_mut(&mut self) -> impl Iterator<Item = &mut [T]> where T: Unpin {
+    let Inner { cur, rest, .. } = self.0.get_mut();
+    rest.iter_mut().map(|c| c.as_mut_slice()).chain(Some(cur.as_mut_slice()))
+  }
+
+  /// Returns a mutable reference to the most recently allocated element, or `None` if the arena is empty.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let mut a = SimpleArena::new();
+  /// assert_eq!(a.last_mut(), None);
+  /// a.alloc(1);
+  /// assert_eq!(a.last_mut(), Some(&mut 1));
+  /// // the first chunk is full, and the current chunk is empty after this
+  /// a.reserve(10);
+  /// *a.last_mut().unwrap() += 10;
+  /// a.alloc(2);
+  /// assert_eq!(a.last_mut(), Some(&mut 2));
+  /// a.alloc_uninit();
+  /// assert_eq!(a.last_mut(), Some(&mut 2));
+  /// assert_eq!(a.into_vec(), [11, 2]);
+  /// ```
+  #[inline]
+  pub fn last_mut(&mut self) -> Option<&mut T> where T: Unpin { self.iter_mut().next_back() }
+
+  /// Returns a reference to the `i`-th allocated element, or `None` if `i >= len()`.
+  ///
+  /// Elements are indexed in allocation order, as yielded by `iter_mut`, and an index stays valid
+  /// until the arena is cleared or rolled back before it, see `alloc_idx`. Elements of the current chunk are found
+  /// in `O(1)` time, and others by a binary search over the retired chunks, in `O(log chunk_count())` time.
+  /// The chunk can't be computed from the index alone, since chunk sizes are not always powers of two,
+  /// e.g. after `with_capacity`, `reserve` or `alloc_slice`.
+  ///
+  /// Indices are `usize` like those of slices, and can be stored as `u32` when the arena is known to be small enough.
+  /// There is no `Index` impl: `index` takes `&self`, while a mutable reference returned by `alloc` to the same
+  /// element may still be alive, which the borrow checker rules out here by `&mut self`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let mut a = SimpleArena::new();
+  /// let mut idx = Vec::new();
+  /// for i in 0..300 {
+  ///   idx.push(a.alloc_idx(i).0);
+  ///   if i % 7 == 0 { a.alloc_slice((0..i % 5).map(|x| x + 1000).collect()); }
+  /// }
+  /// assert!(a.chunk_count() > 5);
+  /// assert!(idx.iter().enumerate().all(|(i, &j)| a.get(j) == Some(&i)));
+  /// assert_eq!(a.get(a.len()), None);
+  /// *a.get_mut(idx[150]).unwrap() = 0;
+  /// assert_eq!(a.into_vec()[idx[150]], 0);
+  /// ```
+  #[inline]
+  pub fn get(&mut self, i: usize) -> Option<&T> { self.0.get_mut().get(i).map(|x| unsafe { &*x }) }
+
+  /// Returns a mutable reference to the `i`-th allocated element, or `None` if `i >= len()`.
+  ///
+  /// See `get` for details.
+  #[inline]
+  pub fn get_mut(&mut self, i: usize) -> Option<&mut T> where T: Unpin {
+    self.0.get_mut().get(i).map(|x| unsafe { &mut *x })
+  }
+
+  /// Returns a pointer to the `i`-th allocated element, or `None` if `i >= len()`, without borrowing other elements.
+  #[inline]
+  pub(crate) fn get_ptr(&self, i: usize) -> Option<NonNull<T>> {
+    let inner = unsafe { &*self.0.get() };
+    inner.get(i).map(|x| unsafe { NonNull::new_unchecked(x) })
+  }
+
+  /// Returns a pointer to the `k`-th chunk in allocation order and its length, or `None` if `k > rest.len()`,
+  /// without borrowing its elements.
+  ///
+  /// Chunks are only ever appended, unless the arena is accessed by `&mut`, so the `k`-th chunk stays the same.
+  #[inline]
+  pub(crate) fn chunk_ptr(&self, k: usize) -> Option<(NonNull<T>, usize)> {
+    let Inner { cur, rest, .. } = unsafe { &*self.0.get() };
+    let c = if k < rest.len() { &rest[k] } else if k == rest.len() { cur } else { return None };
+    Some((unsafe { NonNull::new_unchecked(c.ptr.as_ptr()) }, c.len))
+  }
+
+  /// Returns `true` if `x` refers to an element allocated in this arena.
+  ///
+  /// `x` is never dereferenced: its address is compared with the initialized part of each chunk,
+  /// which takes `O(chunk_count())` time. Always returns `false` for zero-sized types,
+  /// since they have no address to check.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let (a, b) = (SimpleArena::new(), SimpleArena::new());
+  /// // the first and last element of a chunk of 1, 2 and 4 elements
+  /// let x: Vec<&u64> = (0..7).map(|i| &*a.alloc(i)).collect();
+  /// let y = &*b.alloc(1);
+  /// assert!(x.iter().all(|x| a.contains(x)));
+  /// assert!(!a.contains(y) && b.contains(y));
+  /// assert!(!a.contains(&1));
+  /// let u = a.alloc_uninit().write(4);
+  /// assert!(!a.contains(u));
+  /// ```
+  pub fn contains(&self, x: &T) -> bool {
+    let Inner { cur, rest, .. } = unsafe { &*self.0.get() };
+    let (addr, size) = (x as *const T as usize, mem::size_of::<T>());
+    rest.iter().chain(Some(cur)).any(|c| {
+      let start = c.ptr.as_ptr() as usize;
+      (start..start + c.len * size).contains(&addr) && (addr - start).is_multiple_of(size)
+    })
+  }
+
+  /// Returns a wrapper whose `Debug` output lists the first `n` elements, in allocation order.
+  ///
+  /// It takes `&mut self`, since references returned by `alloc` may still be used to modify the elements otherwise.
+  /// The `Debug` output of the arena itself only shows its length and memory usage.
+  #[inline]
+  pub fn debug_elements(&mut self, n: usize) -> DebugElements<'_, T, A> { DebugElements { arena: self, n } }
+
+  /// Calls `f` on each element, in allocation order.
+  ///
+  /// It takes O(n) time and allocates nothing, so no list of references needs to be kept for a finalization pass.
+  /// If `f` panics, the arena is left intact, and can still be used and dropped.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  /// use std::panic::{self, AssertUnwindSafe};
+  ///
+  /// let mut a = SimpleArena::new();
+  /// for i in 1..=10 { a.alloc(i); }
+  /// let mut v = Vec::new();
+  /// a.for_each(|x| v.push(*x));
+  /// assert!(v.into_iter().eq(1..=10));
+  /// let r = panic::catch_unwind(AssertUnwindSafe(|| a.for_each(|x| if *x == 5 { panic!() })));
+  /// assert!(r.is_err());
+  /// assert_eq!(a.into_vec().len(), 10);
+  /// ```
+  #[inline]
+  pub fn for_each(&mut self, mut f: impl FnMut(&T)) {
+    let Inner { cur, rest, .. } = self.0.get_mut();
+    for c in rest.iter_mut().chain(Some(cur)) { c.as_mut_slice().iter().for_each(&mut f); }
+  }
+
+  /// Calls `f` on a mutable reference to each element, in allocation order.
+  ///
+  /// See `for_each` for details.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let mut a = SimpleArena::new();
+  /// a.alloc(1);
+  /// a.alloc_slice(vec![2, 3]);
+  /// a.for_each_mut(|x| *x *= 10);
+  /// assert_eq!(a.into_vec(), [10, 20, 30]);
+  /// ```
+  #[inline]
+  pub fn for_each_mut(&mut self, f: impl FnMut(&mut T)) where T: Unpin { self.iter_mut().for_each(f) }
+
+  /// Consumes the arena, and returns a `Vec` containing all elements in allocation order.
+  ///
+  /// Elements are moved, not cloned, and the chunks are freed.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  /// use std::rc::Rc;
+  ///
+  /// let r = Rc::new(());
+  /// assert!(SimpleArena::<(usize, Rc<()>)>::new().into_vec().is_empty());
+  /// let a = SimpleArena::new();
+  /// // the current chunk is exactly full
+  /// for i in 0..7 { a.alloc((i, r.clone())); }
+  /// let v = a.into_vec();
+  /// assert_eq!(Rc::strong_count(&r), 8);
+  /// assert!(v.iter().map(|d| d.0).eq(0..7));
+  /// drop(v);
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  /// ```
+  pub fn into_vec(self) -> Vec<T> where T: Unpin {
+    let Inner { cur, rest, free, align, alloc, .. } = unsafe { ptr::read(self.0.get()) };
+    mem::forget(self);
+    let mut v: Vec<T> = Vec::with_capacity(rest.iter().map(|c| c.len).sum::<usize>() + cur.len);
+    for c in rest.into_iter().chain(Some(cur)).chain(free) {
+      unsafe {
+        ptr::copy_nonoverlapping(c.ptr.as_ptr(), v.as_mut_ptr().add(v.len()), c.len);
+        v.set_len(v.len() + c.len);
+        c.dealloc(&alloc, align);
+      }
+    }
+    v
+  }
+
+  /// Consumes the arena without ever dropping its elements or freeing its memory, and returns a reference to it
+  /// that lives as long as `'a`, which may be `'static`.
+  ///
+  /// References returned by allocation methods through it live as long as `'a` too, so values can be
+  /// allocated for the rest of the program, like in an interner. All memory of the arena, including the arena itself,
+  /// which is moved into a `Box`, is leaked, and is only reclaimed by the operating system when the process exits.
+  /// The arena keeps growing as it is used, so this is intended for arenas created once.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  /// # use std::sync::atomic::{AtomicPtr, Ordering};
+  /// # // keep the leaked arena reachable from a static, so that Miri doesn't report it as leaked
+  /// # static LEAKED: AtomicPtr<SimpleArena<String>> = AtomicPtr::new(std::ptr::null_mut());
+  ///
+  /// fn intern(a: &'static SimpleArena<String>, names: &[&str]) -> Vec<&'static str> {
+  ///   names.iter().map(|s| a.alloc(s.to_string()).as_str()).collect()
+  /// }
+  ///
+  /// let (names, more) = {
+  ///   let a = SimpleArena::new();
+  ///   let a: &'static SimpleArena<String> = a.leak();
+  /// # LEAKED.store(a as *const _ as *mut _, Ordering::Relaxed);
+  ///   (intern(a, &["foo", "bar"]), intern(a, &["baz"]))
+  /// };
+  /// // the arena value is out of scope, but the strings it owns are never freed
+  /// assert_eq!((names, more), (vec!["foo", "bar"], vec!["baz"]));
+  /// ```
+  pub fn leak<'a>(self) -> &'a mut Self where T: 'a, A: 'a { Box::leak(Box::new(self)) }
+
+  /// Drops all elements in the arena, and keeps all chunks for reuse.
+  ///
+  /// Later allocations fill the chunks again in the order they were first allocated,
+  /// so refilling the arena with no more elements than before never allocates.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  /// use std::rc::Rc;
+  ///
+  /// let r = Rc::new(());
+  /// let mut a = SimpleArena::new();
+  /// let p = (0..100).map(|_| a.alloc(r.clone()) as *mut Rc<()>).collect::<Vec<_>>();
+  /// a.clear();
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  /// assert!(a.is_empty());
+  /// for _ in 0..2 {
+  ///   for i in 0..50 { assert_eq!(a.alloc(r.clone()) as *mut Rc<()>, p[i]); }
+  ///   a.clear();
+  /// }
+  /// for i in 0..127 { assert_eq!(a.alloc(r.clone()) as *mut Rc<()>, p[i.min(99)].wrapping_add(i.saturating_sub(99))); }
+  /// assert_eq!(Rc::strong_count(&r), 128);
+  /// drop(a);
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  /// ```
+  pub fn clear(&mut self) {
+    let inner = self.0.get_mut();
+    for c in &mut inner.rest {
+      inner.retired -= c.len;
+      c.end = c.cap;
+      unsafe { c.truncate(0); }
+    }
+    inner.cur.end = inner.cur.cap;
+    unsafe { inner.cur.truncate(0); }
+    // order all chunks from the last to use to the first
+    let mut chunks = mem::take(&mut inner.free);
+    chunks.push(mem::replace(&mut inner.cur, Chunk::empty()));
+    chunks.extend(inner.rest.drain(..).rev());
+    inner.ends.clear();
+    inner.cur = chunks.pop().unwrap();
+    inner.free = chunks;
+  }
+
+  /// Moves all elements out of the arena into a `Vec`, in allocation order, and keeps all chunks for reuse like `clear`.
+  ///
+  /// Unlike `into_vec`, the arena can still be used afterwards, and refilling it doesn't allocate new chunks.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  /// use std::rc::Rc;
+  ///
+  /// let r = Rc::new(());
+  /// let mut a = SimpleArena::new();
+  /// let p = (0..10).map(|i| a.alloc((i, r.clone())) as *mut (usize, Rc<()>)).collect::<Vec<_>>();
+  /// for _ in 0..3 {
+  ///   let v = a.take_all();
+  ///   assert!(v.iter().map(|d| d.0).eq(0..10));
+  ///   assert_eq!(Rc::strong_count(&r), 11);
+  ///   drop(v);
+  ///   assert!(a.is_empty());
+  ///   for i in 0..10 { assert_eq!(a.alloc((i, r.clone())) as *mut (usize, Rc<()>), p[i]); }
+  /// }
+  /// drop(a);
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  /// ```
+  pub fn take_all(&mut self) -> Vec<T> where T: Unpin {
+    let len = self.len();
+    let Inner { cur, rest, .. } = self.0.get_mut();
+    let mut v: Vec<T> = Vec::with_capacity(len);
+    for c in rest.iter_mut().chain(Some(cur)) {
+      unsafe {
+        ptr::copy_nonoverlapping(c.ptr.as_ptr(), v.as_mut_ptr().add(v.len()), c.len);
+        v.set_len(v.len() + c.len);
+      }
+      c.len = 0;
+    }
+    self.0.get_mut().retired = 0;
+    self.clear();
+    v
+  }
+
+  /// Drops all elements in the arena, frees all chunks except the largest one, and returns the number of bytes freed.
+  ///
+  /// The retained chunk becomes the current chunk, and later chunks double from its capacity.
+  /// Unlike `clear`, a spike in usage doesn't keep the arena large across cycles.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let mut a = SimpleArena::new();
+  /// for i in 0..10u64 { a.alloc(i); }
+  /// // a spike fills chunks of 1, 2, 4, ..., 512 elements
+  /// for i in 0..990 { a.alloc(i); }
+  /// assert_eq!(a.reset(), (1023 - 512) * 8);
+  /// assert!(a.is_empty());
+  /// let x = a.alloc(0) as *mut u64;
+  /// for i in 1..512 { assert_eq!(a.alloc(i) as *mut u64, x.wrapping_add(i as usize)); }
+  /// // a chunk of 1024 elements
+  /// a.alloc(512);
+  /// assert_eq!(a.reset(), 512 * 8);
+  /// assert_eq!(a.reset(), 0);
+  /// ```
+  pub fn reset(&mut self) -> usize {
+    self.clear();
+    let inner = self.0.get_mut();
+    let mut chunks = mem::take(&mut inner.free);
+    chunks.push(mem::replace(&mut inner.cur, Chunk::empty()));
+    let largest = (0..chunks.len()).max_by_key(|&i| chunks[i].cap).unwrap();
+    inner.cur = chunks.swap_remove(largest);
+    chunks.iter().map(|c| {
+      unsafe { c.dealloc(&inner.alloc, inner.align); }
+      c.cap * mem::size_of::<T>()
+    }).sum()
+  }
+
+  /// Records the current state of the arena, so that it can be restored by `rollback`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let mut a = SimpleArena::new();
+  /// a.alloc_slice(vec![0, 1, 2]);
+  /// let cp = a.checkpoint();
+  /// for i in 3..100 { a.alloc(i); }
+  /// a.rollback(cp);
+  /// assert_eq!(a.len(), 3);
+  /// a.alloc(3);
+  /// a.rollback(cp); // a checkpoint can be used again
+  /// assert!(a.into_vec().into_iter().eq(0..3));
+  /// ```
+  #[inline]
+  pub fn checkpoint(&self) -> SimpleArenaCheckpoint {
+    let inner = unsafe { &mut *self.0.get() };
+    // assigned lazily, so that `new` can be a `const fn`
+    if inner.id == 0 { inner.id = next_id(); }
+    SimpleArenaCheckpoint { id: inner.id, chunk: inner.rest.len(), len: inner.cur.len }
+  }
+
+  /// Drops all elements allocated after `cp` was recorded, and frees the chunks allocated after it.
+  ///
+  /// The chunk that was current when `cp` was recorded becomes the current chunk again, even if it has been retired since.
+  /// The result is unspecified, but memory safe, if `cp` was recorded while the arena was being
+  /// filled by one of its own methods, e.g. inside the closure passed to `alloc_with`.
+  ///
+  /// ## Panics
+  ///
+  /// Panics if `cp` was recorded by another arena, or if it is newer than the state of this arena,
+  /// e.g. because of an earlier `rollback` to an older checkpoint, or `clear`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  /// use std::rc::Rc;
+  ///
+  /// let r = Rc::new(());
+  /// let mut a = SimpleArena::new();
+  /// a.alloc((0, r.clone()));
+  /// a.alloc((1, r.clone()));
+  /// let cp = a.checkpoint();
+  /// // the chunk of `cp` is retired by these
+  /// for i in 2..10 { a.alloc((i, r.clone())); }
+  /// a.rollback(cp);
+  /// assert_eq!((Rc::strong_count(&r), a.len(), a.chunk_count()), (3, 2, 2));
+  ///
+  /// // rolling back to the exact boundary of a chunk, since the second chunk can hold 2 elements
+  /// a.alloc((2, r.clone()));
+  /// let cp = a.checkpoint();
+  /// a.alloc((3, r.clone()));
+  /// assert_eq!(a.chunk_count(), 3);
+  /// let cp2 = a.checkpoint();
+  /// a.rollback(cp);
+  /// assert_eq!((Rc::strong_count(&r), a.len(), a.chunk_count()), (4, 3, 2));
+  /// assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| a.rollback(cp2))).is_err());
+  /// assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| a.rollback(SimpleArena::<u8>::new().checkpoint()))).is_err());
+  /// assert!(a.iter_mut().map(|d| d.0).eq(0..3));
+  /// ```
+  pub fn rollback(&mut self, cp: SimpleArenaCheckpoint) {
+    let Inner { cur, rest, ends, retired, id, align, alloc, .. } = self.0.get_mut();
+    assert_eq!(cp.id, *id, "checkpoint recorded by another arena");
+    let n = rest.len();
+    let chunk_len = if cp.chunk < n { rest[cp.chunk].len } else if cp.chunk == n { cur.len } else { 0 };
+    assert!(cp.chunk <= n && cp.len <= chunk_len, "checkpoint is newer than the arena");
+    let from = |i| if i == cp.chunk { cp.len } else { 0 };
+    for (i, c) in rest.iter_mut().enumerate().skip(cp.chunk) {
+      *retired -= c.len - from(i);
+      unsafe { c.truncate(from(i)); }
+    }
+    unsafe { cur.truncate(from(n)); }
+    if cp.chunk < n {
+      *retired -= cp.len;
+      let mut tail = rest.split_off(cp.chunk + 1);
+      tail.push(mem::replace(cur, rest.pop().unwrap()));
+      ends.truncate(cp.chunk);
+      for c in &tail { unsafe { c.dealloc(alloc, *align); } }
+    }
+  }
+
+  /// Releases the unused capacity of the current chunk, and returns the number of bytes released.
+  ///
+  /// The current chunk is shrunk by the allocator to fit its elements, so they may be moved, and references
+  /// previously returned into it are invalidated, which the borrow checker enforces by `&mut self`.
+  /// Retired chunks are left alone, including the unused tail of a chunk retired early by `reserve` or `alloc_slice`.
+  /// If the current chunk contains slots reserved by `alloc_uninit`, nothing is released. The next allocation will need a new chunk.
+  /// If it holds no element, it is freed, and the next chunk is as large as if it had never been allocated,
+  /// doubling from the last retired chunk.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let mut a = SimpleArena::<u32>::with_capacity(1000);
+  /// a.alloc_slice(vec![1, 2, 3]);
+  /// assert_eq!(a.shrink_to_fit(), 3988);
+  /// assert_eq!(a.allocated_bytes(), 12);
+  /// assert_eq!(a.shrink_to_fit(), 0);
+  /// assert!(a.iter_mut().map(|x| *x).eq(1..4));
+  /// a.alloc(4);
+  /// assert_eq!((a.chunk_count(), a.allocated_bytes()), (2, 36));
+  /// a.alloc_uninit();
+  /// assert_eq!(a.shrink_to_fit(), 0);
+  ///
+  /// let mut a = SimpleArena::<u64>::new();
+  /// for i in 0..7 { a.alloc(i); }
+  /// // the chunks of 1, 2 and 4 elements are full, and the new current chunk of 8 elements is empty
+  /// a.reserve(1);
+  /// assert_eq!(a.shrink_to_fit(), 8 * 8);
+  /// assert_eq!((a.chunk_count(), a.allocated_bytes()), (3, 7 * 8));
+  /// a.alloc(7);
+  /// assert_eq!((a.chunk_count(), a.allocated_bytes()), (4, 15 * 8));
+  /// ```
+  pub fn shrink_to_fit(&mut self) -> usize where T: Unpin {
+    let Inner { cur: c, align, alloc, .. } = self.0.get_mut();
+    let size = mem::size_of::<T>();
+    if c.end != c.cap || c.len == c.cap || size == 0 { return 0; }
+    let released = (c.cap - c.len) * size;
+    unsafe {
+      if c.len == 0 {
+        c.dealloc(alloc, *align);
+        *c = Chunk::empty();
+        return released;
+      }
+      // both layouts were checked by `Inner::try_alloc_chunk`, since the new one is smaller
+      let old = Layout::from_size_align_unchecked(c.cap * size, *align);
+      let new = Layout::from_size_align_unchecked(c.len * size, *align);
+      match alloc.shrink(NonNull::new_unchecked(c.ptr.as_ptr() as *mut u8), old, new) {
+        Ok(p) => {
+          c.ptr = Unique::new_unchecked(p.as_ptr() as *mut T);
+          c.cap = c.len;
+          c.end = c.len;
+          released
+        }
+        Err(_) => 0,
+      }
+    }
+  }
+}
+
+impl<T, A: Allocator> Inner<T, A> {
+  /// Returns a pointer to the `i`-th element, see `SimpleArena::get`.
+  fn get(&self, i: usize) -> Option<*mut T> {
+    // no reference to the chunk is created, since other elements may be borrowed
+    if i >= self.retired {
+      let i = i - self.retired;
+      return if i < self.cur.len { Some(unsafe { self.cur.ptr.as_ptr().add(i) }) } else { None };
+    }
+    let k = self.ends.partition_point(|&e| e <= i);
+    let start = if k == 0 { 0 } else { self.ends[k - 1] };
+    Some(unsafe { self.rest[k].ptr.as_ptr().add(i - start) })
+  }
+
+  /// Allocates a chunk that can hold `cap` elements.
+  #[inline]
+  unsafe fn try_alloc_chunk(&self, cap: usize) -> Result<Unique<T>, AllocError> {
+    // `Layout::array` fails if the size overflows `isize`, which can only happen on 32-bit or 16-bit platforms,
+    // since for 64-bit platform, allocation will fail before that
+    let layout = Layout::array::<T>(cap).and_then(|l| l.align_to(self.align)).map_err(|_| AllocError::capacity_overflow(()))?;
+    if layout.size() == 0 { return Ok(Unique::dangling()); }
+    match self.alloc.allocate(layout) {
+      Ok(p) => Ok(Unique::new_unchecked(p.as_ptr() as *mut T)),
+      Err(_) => Err(AllocError::alloc((), layout)),
+    }
+  }
+
+  /// Retire `cur`, and replace it with a chunk kept by `clear`, or a new chunk twice as large,
+  /// which can hold at least `additional` elements.
+  ///
+  /// A vacant chunk is freed instead of being retired.
+  #[cold]
+  unsafe fn grow(&mut self, additional: usize) {
+    if let Err(e) = self.try_grow(additional) { e.raise() }
+  }
+
+  /// Fallible version of `grow`, which leaves the arena untouched on failure.
+  ///
+  /// If `cur` has room for `additional` elements once settled, it is kept.
+  #[cold]
+  unsafe fn try_grow(&mut self, additional: usize) -> Result<(), AllocError> {
+    self.settle();
+    if self.cur.spare() >= additional { return Ok(()); }
+    if !self.cur.is_vacant() {
+      self.rest.try_reserve(1).map_err(|_| AllocError::alloc((), Layout::new::<[Chunk<T>; 1]>()))?;
+      self.ends.try_reserve(1).map_err(|_| AllocError::alloc((), Layout::new::<[usize; 1]>()))?;
+    }
+    let new = match self.free.last() {
+      Some(c) if c.cap >= additional => self.free.pop().unwrap(),
+      _ => {
+        // an empty current chunk freed by `shrink_to_fit` doesn't restart the doubling
+        let base = if self.cur.cap == 0 { self.rest.last().map_or(0, |c| c.cap) } else { self.cur.cap };
+        let cap = match base.checked_mul(2) {
+          // a chunk of ZSTs never fills up, unless the element count overflows
+          _ if mem::size_of::<T>() == 0 && self.cur.cap == 0 => usize::MAX,
+          Some(cap) => cap.min(self.max_bytes / mem::size_of::<T>()).max(self.min_cap).max(additional),
+          None => return Err(AllocError::capacity_overflow(())),
+        };
+        Chunk::new(self.try_alloc_chunk(cap)?, cap)
+      }
+    };
+    let old = mem::replace(&mut self.cur, new);
+    if old.is_vacant() {
+      old.dealloc(&self.alloc, self.align);
+    } else {
+      self.retired += old.len;
+      self.rest.push(old);
+      self.ends.push(self.retired);
+    }
+    Ok(())
+  }
+}
+
+impl<T, A: Allocator> Chunks for Inner<T, A> {
+  type Item = T;
+
+  #[inline]
+  fn tails(&mut self) -> &mut Tails<T> { &mut self.tails }
+
+  #[inline]
+  fn len(&self) -> usize { self.cur.len }
+
+  #[inline]
+  unsafe fn set_len(&mut self, len: usize) { self.cur.len = len; }
+
+  #[inline]
+  fn end(&mut self) -> &mut usize { &mut self.cur.end }
+
+  #[inline]
+  fn as_mut_ptr(&mut self) -> *mut T { self.cur.ptr.as_ptr() }
+
+  #[inline]
+  fn retired_len(&self) -> usize { self.retired }
+
+  #[inline]
+  fn ends(&mut self) -> &mut [usize] { &mut self.ends }
+
+  #[inline]
+  fn reserve(&mut self, additional: usize) {
+    if self.cur.spare() < additional {
+      unsafe { self.grow(additional); }
+    }
+  }
+
+  fn grow_tail(&mut self) { unreachable!("a `Reserve` of a `SimpleArena` writes at most the elements it reserved") }
+
+  #[inline]
+  unsafe fn forget_retired(&mut self, k: usize, len: usize) {
+    self.rest[k].len = len;
+    for c in &mut self.rest[k + 1..] { c.len = 0; }
+    self.retired = self.ends[self.ends.len() - 1];
+  }
+}
+
+/// The statistics of a chunk of a `SimpleArena`, returned by `SimpleArena::chunk_stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkStat {
+  /// The number of elements the chunk can hold.
+  pub capacity: usize,
+  /// The number of elements allocated in the chunk, not including slots reserved by `alloc_uninit`.
+  pub len: usize,
+  /// The size of the chunk in bytes.
+  pub bytes: usize,
+}
+
+/// A state of a `SimpleArena` recorded by `SimpleArena::checkpoint`, which can be restored by `SimpleArena::rollback`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimpleArenaCheckpoint {
+  id: usize,
+  chunk: usize,
+  len: usize,
+}
+
+impl<T, A: Allocator> fmt::Debug for SimpleArena<T, A> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("SimpleArena")
+      .field("len", &self.len())
+      .field("chunk_count", &self.chunk_count())
+      .field("allocated_bytes", &self.allocated_bytes())
+      .finish()
+  }
+}
+
+/// Prints the first elements of a `SimpleArena`, created by `SimpleArena::debug_elements`.
+pub struct DebugElements<'a, T, A: Allocator = Global> {
+  arena: &'a mut SimpleArena<T, A>,
+  n: usize,
+}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for DebugElements<'_, T, A> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let Inner { cur, rest, .. } = unsafe { &*self.arena.0.get() };
+    let mut l = f.debug_list();
+    l.entries(rest.iter().chain(Some(cur)).flat_map(Chunk::as_slice).take(self.n));
+    if self.arena.len() > self.n { l.finish_non_exhaustive() } else { l.finish() }
   }
 }
 
@@ -80,19 +1731,224 @@ impl<T> Default for SimpleArena<T> {
   fn default() -> Self { Self::new() }
 }
 
-unsafe impl<#[may_dangle] T> Drop for SimpleArena<T> {
+impl<T, A: Allocator> Extend<T> for SimpleArena<T, A> {
+  /// Allocates all values yielded by `iter` in the arena one by one, like `alloc`.
+  ///
+  /// The current chunk is made to fit the lower bound of the iterator's `size_hint` first, so an iterator of known length
+  /// allocates at most one chunk. This is also implemented for `&mut SimpleArena`.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// fn fill(mut e: impl Extend<u32>, n: u32) { e.extend(0..n); }
+  ///
+  /// let mut a = SimpleArena::new();
+  /// a.extend(None);
+  /// assert_eq!((a.len(), a.chunk_count()), (0, 0));
+  /// fill(&mut a, 100);
+  /// assert_eq!(a.chunk_count(), 1);
+  /// // the length of a filtered iterator is unknown, so chunks grow as usual
+  /// a.extend((100..1000).filter(|_| true));
+  /// assert!(a.chunk_count() > 3);
+  /// a.extend(Vec::new());
+  /// assert!(a.into_vec().into_iter().eq(0..1000));
+  /// ```
+  fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+    let iter = iter.into_iter();
+    self.reserve(iter.size_hint().0);
+    for t in iter { self.alloc(t); }
+  }
+}
+
+impl<T> FromIterator<T> for SimpleArena<T> {
+  /// Creates an arena holding all values yielded by `iter` in order, like `extend` on a new arena.
+  ///
+  /// The first chunk is sized by the lower bound of the iterator's `size_hint`, so collecting an iterator of known length
+  /// allocates exactly one chunk.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  ///
+  /// let mut a: SimpleArena<_> = (0..10000).map(|x| x * 2).collect();
+  /// assert_eq!((a.len(), a.chunk_count()), (10000, 1));
+  /// assert_eq!(a.get(1234), Some(&2468));
+  /// let a: SimpleArena<_> = "arena".chars().collect();
+  /// assert_eq!(a.into_vec(), ['a', 'r', 'e', 'n', 'a']);
+  /// ```
+  fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+    let mut a = Self::new();
+    a.extend(iter);
+    a
+  }
+}
+
+impl<T, A: Allocator> Extend<T> for &mut SimpleArena<T, A> {
+  /// See `Extend` for `SimpleArena`.
+  #[inline]
+  fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) { (**self).extend(iter) }
+}
+
+impl<T: Unpin, A: Allocator> IntoIterator for SimpleArena<T, A> {
+  type Item = T;
+  type IntoIter = IntoIter<T, A>;
+
+  /// Consumes the arena, and returns an iterator yielding all elements by value in allocation order.
+  ///
+  /// No intermediate `Vec` is allocated, and each chunk is freed once all its elements are yielded.
+  /// If the iterator is dropped, the remaining elements are dropped.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SimpleArena;
+  /// use std::{panic::{self, AssertUnwindSafe}, rc::Rc};
+  ///
+  /// let r = Rc::new(());
+  /// let a = SimpleArena::new();
+  /// for i in 0..10 { a.alloc((i, r.clone())); }
+  /// let mut it = a.into_iter();
+  /// assert_eq!(it.len(), 10);
+  /// assert!(it.by_ref().take(4).map(|d| d.0).eq(0..4));
+  /// assert_eq!(Rc::strong_count(&r), 7);
+  /// drop(it);
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  ///
+  /// let a = SimpleArena::new();
+  /// for i in 0..10 { a.alloc((i, r.clone())); }
+  /// let res = panic::catch_unwind(AssertUnwindSafe(|| for d in a { if d.0 == 5 { panic!() } }));
+  /// assert!(res.is_err());
+  /// assert_eq!(Rc::strong_count(&r), 1);
+  /// ```
+  fn into_iter(self) -> IntoIter<T, A> {
+    let len = self.len();
+    let Inner { cur, mut rest, free, align, alloc, .. } = unsafe { ptr::read(self.0.get()) };
+    mem::forget(self);
+    rest.push(cur);
+    rest.extend(free);
+    IntoIter { chunks: rest.into_iter(), front: None, pos: 0, len, align, alloc }
+  }
+}
+
+/// Iterator yielding the elements of a `SimpleArena` by value, created by `SimpleArena::into_iter`.
+pub struct IntoIter<T, A: Allocator = Global> {
+  chunks: vec::IntoIter<Chunk<T>>,
+  // the chunk being yielded, whose elements before `pos` are moved out
+  front: Option<Chunk<T>>,
+  pos: usize,
+  len: usize,
+  align: usize,
+  alloc: A,
+}
+
+impl<T, A: Allocator> Iterator for IntoIter<T, A> {
+  type Item = T;
+
+  #[inline]
+  fn next(&mut self) -> Option<T> {
+    loop {
+      if let Some(c) = &self.front {
+        if self.pos < c.len {
+          let x = unsafe { ptr::read(c.ptr.as_ptr().add(self.pos)) };
+          self.pos += 1;
+          self.len -= 1;
+          return Some(x);
+        }
+        unsafe { c.dealloc(&self.alloc, self.align); }
+        self.front = None;
+      }
+      self.front = Some(self.chunks.next()?);
+      self.pos = 0;
+    }
+  }
+
+  #[inline]
+  fn size_hint(&self) -> (usize, Option<usize>) { (self.len, Some(self.len)) }
+}
+
+impl<T, A: Allocator> ExactSizeIterator for IntoIter<T, A> {}
+
+impl<T, A: Allocator> Drop for IntoIter<T, A> {
   fn drop(&mut self) {
     unsafe {
-      let Inner { cur, rest } = &mut *self.0.get();
-      let (size, align) = (mem::size_of::<T>(), mem::align_of::<T>());
-      for (idx, p) in rest.iter().enumerate() {
-        let cap = 1 << idx;
-        ptr::drop_in_place(slice::from_raw_parts_mut(p.as_ptr(), cap) as _);
-        dealloc(p.as_ptr() as _, Layout::from_size_align_unchecked(cap, align));
+      if let Some(c) = &self.front {
+        ptr::drop_in_place(ptr::slice_from_raw_parts_mut(c.ptr.as_ptr().add(self.pos), c.len - self.pos));
+        c.dealloc(&self.alloc, self.align);
+      }
+      for c in &mut self.chunks {
+        ptr::drop_in_place(ptr::slice_from_raw_parts_mut(c.ptr.as_ptr(), c.len));
+        c.dealloc(&self.alloc, self.align);
+      }
+    }
+  }
+}
+
+/// Iterator over mutable references to the elements of a `SimpleArena`, created by `SimpleArena::iter_mut`.
+pub struct IterMut<'a, T> {
+  chunks: slice::IterMut<'a, Chunk<T>>,
+  front: slice::IterMut<'a, T>,
+  back: slice::IterMut<'a, T>,
+  len: usize,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+  type Item = &'a mut T;
+
+  #[inline]
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      if let Some(x) = self.front.next() {
+        self.len -= 1;
+        return Some(x);
+      }
+      match self.chunks.next() {
+        Some(chunk) => self.front = chunk.as_mut_slice().iter_mut(),
+        None => {
+          let x = self.back.next()?;
+          self.len -= 1;
+          return Some(x);
+        }
       }
-      let p = cur.0.as_ptr();
-      ptr::drop_in_place(slice::from_raw_parts_mut(p, cur.1) as _);
-      dealloc(p as _, Layout::from_size_align_unchecked(size * (1 << rest.len()), align));
     }
   }
-}
\ No newline at end of file
+
+  #[inline]
+  fn size_hint(&self) -> (usize, Option<usize>) { (self.len, Some(self.len)) }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+  #[inline]
+  fn next_back(&mut self) -> Option<Self::Item> {
+    loop {
+      if let Some(x) = self.back.next_back() {
+        self.len -= 1;
+        return Some(x);
+      }
+      match self.chunks.next_back() {
+        Some(chunk) => self.back = chunk.as_mut_slice().iter_mut(),
+        None => {
+          let x = self.front.next_back()?;
+          self.len -= 1;
+          return Some(x);
+        }
+      }
+    }
+  }
+}
+
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {}
+
+unsafe impl<#[may_dangle] T, A: Allocator> Drop for SimpleArena<T, A> {
+  fn drop(&mut self) {
+    unsafe {
+      let Inner { cur, rest, free, align, alloc, .. } = self.0.get_mut();
+      for c in rest.iter().chain(Some(&*cur)).chain(free.iter()) {
+        ptr::drop_in_place(ptr::slice_from_raw_parts_mut(c.ptr.as_ptr(), c.len));
+        c.dealloc(alloc, *align);
+      }
+    }
+  }
+}
@@ -0,0 +1,171 @@
+use core::{cell::Cell, mem};
+use crate::SimpleArena;
+
+/// An arena of objects of type `T`, whose objects can be freed one by one, and whose slots are reused.
+///
+/// Allocating returns a `SlotId` instead of a reference. `free` moves the object out, and puts its slot at the head of
+/// a free list threaded through the vacant slots, so the next `alloc` reuses it. Slots are never moved,
+/// and are stored in a `SimpleArena`. Dropping the arena drops only the objects that are not freed.
+///
+/// Once a slot is reused, an id of its previous object refers to the new one. Use `GenArena` if that must be detected.
+///
+/// ## Example
+///
+/// ```
+/// use arena::SlabArena;
+///
+/// let mut a = SlabArena::new();
+/// let x = a.alloc("x".to_string());
+/// let y = a.alloc("y".to_string());
+/// assert_eq!(a.free(x), Some("x".to_string()));
+/// assert_eq!(a.get(x), None);
+/// // the slot of `x` is reused
+/// let z = a.alloc("z".to_string());
+/// assert_eq!(z, x);
+/// a.get_mut(y).unwrap().push('!');
+/// assert_eq!((a.get(y).unwrap().as_str(), a.get(z).unwrap().as_str(), a.len()), ("y!", "z", 2));
+/// ```
+///
+/// Only the objects that are not freed are dropped with the arena:
+///
+/// ```
+/// use arena::SlabArena;
+/// use std::rc::Rc;
+///
+/// let r = Rc::new(());
+/// let mut a = SlabArena::new();
+/// let ids: Vec<_> = (0..10).map(|_| a.alloc(r.clone())).collect();
+/// for &i in &ids[..4] { drop(a.free(i)); }
+/// assert_eq!(Rc::strong_count(&r), 7);
+/// drop(a);
+/// assert_eq!(Rc::strong_count(&r), 1);
+/// ```
+pub struct SlabArena<T> {
+  slots: SimpleArena<Entry<T>>,
+  // the index of the first vacant slot, or `NIL`
+  free: Cell<usize>,
+  len: Cell<usize>,
+}
+
+pub(crate) enum Entry<T> {
+  Occupied(T),
+  // the index of the next vacant slot, or `NIL`
+  Vacant(usize),
+}
+
+pub(crate) const NIL: usize = usize::MAX;
+
+/// The id of a slot in a `SlabArena`, returned by `SlabArena::alloc`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SlotId(usize);
+
+impl SlotId {
+  /// Returns the index of the slot, which is less than the number of slots ever used by the arena.
+  #[inline]
+  pub const fn index(self) -> usize { self.0 }
+}
+
+impl<T> SlabArena<T> {
+  /// Creates an empty arena.
+  #[inline]
+  pub fn new() -> Self { SlabArena { slots: SimpleArena::new(), free: Cell::new(NIL), len: Cell::new(0) } }
+
+  /// Creates an arena whose first chunk can hold `n` objects.
+  #[inline]
+  pub fn with_capacity(n: usize) -> Self {
+    SlabArena { slots: SimpleArena::with_capacity(n), free: Cell::new(NIL), len: Cell::new(0) }
+  }
+
+  /// Allocates a value in the arena, and returns its id.
+  ///
+  /// The most recently freed slot is reused, otherwise a new slot is allocated.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SlabArena;
+  ///
+  /// let mut a = SlabArena::new();
+  /// let ids: Vec<_> = (0..10).map(|i| a.alloc(i)).collect();
+  /// for &i in &ids[2..5] { a.free(i); }
+  /// // reused in the reverse order of freeing
+  /// let again: Vec<_> = (0..4).map(|i| a.alloc(i + 10).index()).collect();
+  /// assert_eq!(again, [4, 3, 2, 10]);
+  /// assert_eq!(a.len(), 11);
+  /// ```
+  pub fn alloc(&self, t: T) -> SlotId {
+    let i = self.free.get();
+    let id = if i == NIL {
+      SlotId(self.slots.alloc_idx(Entry::Occupied(t)).0)
+    } else {
+      unsafe {
+        let p = self.slots.get_ptr(i).unwrap().as_ptr();
+        match *p {
+          Entry::Vacant(next) => self.free.set(next),
+          Entry::Occupied(_) => unreachable!(),
+        }
+        p.write(Entry::Occupied(t));
+      }
+      SlotId(i)
+    };
+    self.len.set(self.len.get() + 1);
+    id
+  }
+
+  /// Frees the object of `id`, and returns it, or returns `None` if the slot is already vacant.
+  ///
+  /// ## Example
+  ///
+  /// Freeing an object twice is detected:
+  ///
+  /// ```
+  /// use arena::SlabArena;
+  ///
+  /// let mut a = SlabArena::new();
+  /// let x = a.alloc(vec![1]);
+  /// assert_eq!(a.free(x), Some(vec![1]));
+  /// assert_eq!(a.free(x), None);
+  /// assert!(a.is_empty());
+  /// ```
+  pub fn free(&mut self, id: SlotId) -> Option<T> {
+    let p = self.slots.get_ptr(id.0)?.as_ptr();
+    let e = unsafe { &mut *p };
+    if let Entry::Vacant(_) = e { return None; }
+    self.len.set(self.len.get() - 1);
+    match mem::replace(e, Entry::Vacant(self.free.replace(id.0))) {
+      Entry::Occupied(t) => Some(t),
+      Entry::Vacant(_) => unreachable!(),
+    }
+  }
+
+  /// Returns a reference to the object of `id`, or `None` if it is freed.
+  #[inline]
+  pub fn get(&self, id: SlotId) -> Option<&T> {
+    match unsafe { &*self.slots.get_ptr(id.0)?.as_ptr() } {
+      Entry::Occupied(t) => Some(t),
+      Entry::Vacant(_) => None,
+    }
+  }
+
+  /// Returns a mutable reference to the object of `id`, or `None` if it is freed.
+  #[inline]
+  pub fn get_mut(&mut self, id: SlotId) -> Option<&mut T> {
+    match unsafe { &mut *self.slots.get_ptr(id.0)?.as_ptr() } {
+      Entry::Occupied(t) => Some(t),
+      Entry::Vacant(_) => None,
+    }
+  }
+
+  /// Returns the number of objects that are not freed.
+  #[inline]
+  pub fn len(&self) -> usize { self.len.get() }
+
+  /// Returns `true` if every object is freed.
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.len() == 0 }
+}
+
+impl<T> Default for SlabArena<T> {
+  /// Equivalent to calling `SlabArena::<T>::new()`.
+  fn default() -> Self { Self::new() }
+}
@@ -0,0 +1,75 @@
+use core::str;
+use crate::DroplessArena;
+
+/// An arena of strings.
+///
+/// Each string is copied into one chunk, so it stays contiguous, though different strings may live in different chunks.
+/// A string larger than a regular chunk gets a chunk of its own.
+///
+/// ## Example
+///
+/// An AST whose identifiers borrow from a `StrArena`, so that it can outlive the source code:
+///
+/// ```
+/// use arena::StrArena;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Expr<'a> { Var(&'a str), Call(&'a str, Vec<Expr<'a>>) }
+///
+/// fn parse<'a>(names: &'a StrArena, src: &str) -> Expr<'a> {
+///   match src.find('(') {
+///     Some(i) => {
+///       let args = src[i + 1..src.len() - 1].split(',').map(|s| parse(names, s.trim())).collect();
+///       Expr::Call(names.alloc_str(&src[..i]), args)
+///     }
+///     None => Expr::Var(names.alloc_str(src)),
+///   }
+/// }
+///
+/// let names = StrArena::new();
+/// let src = String::from("add(x, neg(y))");
+/// let e = parse(&names, &src);
+/// drop(src);
+/// assert_eq!(e, Expr::Call("add", vec![Expr::Var("x"), Expr::Call("neg", vec![Expr::Var("y")])]));
+/// ```
+pub struct StrArena(DroplessArena<u8>);
+
+impl StrArena {
+  /// Creates an empty arena, which doesn't allocate until the first allocation.
+  #[inline]
+  pub const fn new() -> Self { StrArena(DroplessArena::new()) }
+
+  /// Creates an arena whose first chunk can hold `bytes` bytes.
+  #[inline]
+  pub fn with_capacity(bytes: usize) -> Self { StrArena(DroplessArena::with_capacity(bytes)) }
+
+  /// Copies a string into the arena, and returns a mutable reference to the copy.
+  ///
+  /// The empty string takes no memory.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::StrArena;
+  ///
+  /// let a = StrArena::new();
+  /// let x = a.alloc_str("hello");
+  /// x.make_ascii_uppercase();
+  /// assert_eq!(x, "HELLO");
+  /// assert_eq!(a.alloc_str(""), "");
+  /// // a long string doesn't make the current chunk retire early
+  /// let long = "long".repeat(10000);
+  /// assert_eq!(a.alloc_str(&long), &long[..]);
+  /// let y = a.alloc_str("world");
+  /// assert_eq!(y.as_ptr(), x.as_ptr().wrapping_add(5));
+  /// ```
+  #[inline]
+  pub fn alloc_str(&self, s: &str) -> &mut str {
+    unsafe { str::from_utf8_unchecked_mut(self.0.alloc_slice_copy(s.as_bytes())) }
+  }
+}
+
+impl Default for StrArena {
+  /// Equivalent to calling `StrArena::new()`.
+  fn default() -> Self { Self::new() }
+}
@@ -0,0 +1,115 @@
+use core::{fmt, ops::{Deref, DerefMut}, str};
+use crate::{Bump, ArenaVec};
+
+/// A growable string, whose buffer is allocated in a `Bump`.
+///
+/// It grows like `ArenaVec`: in place if it is the most recent allocation in the arena, and otherwise by moving
+/// to a new buffer, leaving the old one wasted. An empty string doesn't allocate.
+///
+/// ## Example
+///
+/// ```
+/// use arena::{Bump, ArenaString};
+/// use std::fmt::Write;
+///
+/// let b = Bump::new();
+/// let names: Vec<&str> = (0..3).map(|i| {
+///   let mut s = ArenaString::new_in(&b);
+///   write!(s, "_ZN{}f{}E", i, i * 10).unwrap();
+///   &*s.into_str()
+/// }).collect();
+/// assert_eq!(names, ["_ZN0f0E", "_ZN1f10E", "_ZN2f20E"]);
+/// ```
+pub struct ArenaString<'a>(ArenaVec<'a, u8>);
+
+impl<'a> ArenaString<'a> {
+  /// Creates an empty string in `bump`, which doesn't allocate until the first character is pushed.
+  #[inline]
+  pub fn new_in(bump: &'a Bump) -> Self { ArenaString(ArenaVec::new_in(bump)) }
+
+  /// Creates an empty string in `bump`, with room for `bytes` bytes.
+  #[inline]
+  pub fn with_capacity_in(bytes: usize, bump: &'a Bump) -> Self { ArenaString(ArenaVec::with_capacity_in(bytes, bump)) }
+
+  /// Returns the length of the string in bytes.
+  #[inline]
+  pub fn len(&self) -> usize { self.0.len() }
+
+  /// Returns `true` if the string is empty.
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.0.is_empty() }
+
+  /// Returns the number of bytes the string can hold without growing.
+  #[inline]
+  pub fn capacity(&self) -> usize { self.0.capacity() }
+
+  /// Returns the contents as a `&str`.
+  #[inline]
+  pub fn as_str(&self) -> &str { unsafe { str::from_utf8_unchecked(&self.0) } }
+
+  /// Appends a character to the back of the string.
+  #[inline]
+  pub fn push(&mut self, c: char) {
+    self.0.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+  }
+
+  /// Appends a string slice to the back of the string.
+  #[inline]
+  pub fn push_str(&mut self, s: &str) { self.0.extend_from_slice(s.as_bytes()); }
+
+  /// Consumes the string, and returns it as a `&mut str` that lives as long as the arena.
+  ///
+  /// The spare capacity is given back to the arena if the buffer is the most recent allocation, otherwise it is wasted.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::{Bump, ArenaString};
+  ///
+  /// let b = Bump::new();
+  /// assert_eq!(ArenaString::new_in(&b).into_str(), "");
+  /// assert_eq!(b.allocated_bytes(), 0);
+  /// let mut s = ArenaString::with_capacity_in(100, &b);
+  /// s.push_str("abc");
+  /// s.push('é');
+  /// let s = s.into_str();
+  /// assert_eq!((&*s, s.len()), ("abcé", 5));
+  /// assert_eq!(b.alloc(0u8) as *mut u8, s.as_mut_ptr().wrapping_add(5));
+  /// assert_eq!(b.wasted_bytes(), 0);
+  /// ```
+  #[inline]
+  pub fn into_str(self) -> &'a mut str { unsafe { str::from_utf8_unchecked_mut(self.0.into_slice()) } }
+}
+
+impl Deref for ArenaString<'_> {
+  type Target = str;
+  #[inline]
+  fn deref(&self) -> &str { self.as_str() }
+}
+
+impl DerefMut for ArenaString<'_> {
+  #[inline]
+  fn deref_mut(&mut self) -> &mut str { unsafe { str::from_utf8_unchecked_mut(&mut self.0) } }
+}
+
+impl fmt::Write for ArenaString<'_> {
+  #[inline]
+  fn write_str(&mut self, s: &str) -> fmt::Result {
+    self.push_str(s);
+    Ok(())
+  }
+
+  #[inline]
+  fn write_char(&mut self, c: char) -> fmt::Result {
+    self.push(c);
+    Ok(())
+  }
+}
+
+impl fmt::Debug for ArenaString<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { fmt::Debug::fmt(self.as_str(), f) }
+}
+
+impl fmt::Display for ArenaString<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { fmt::Display::fmt(self.as_str(), f) }
+}
@@ -0,0 +1,142 @@
+use std::{boxed::Box, vec::Vec, sync::{Mutex, atomic::{AtomicPtr, AtomicUsize, Ordering}}};
+use core::{marker::PhantomData, mem::{self, MaybeUninit}, ptr::{self, NonNull}};
+use crate::dropless::{PAGE, HUGE_PAGE};
+
+/// An arena of objects of type `T`, which can be shared between threads.
+///
+/// Each thread claims a slot by atomically bumping the index of the current chunk, so allocations from different threads
+/// never overlap, and handing out `&mut T` from `&self` stays sound. Only when the current chunk is full, one thread takes
+/// a lock to allocate a new chunk, and the others wait for it. The objects are dropped when the arena is dropped.
+///
+/// ## Example
+///
+/// Many threads allocating into one arena:
+///
+/// ```
+/// use arena::SyncArena;
+/// use std::{sync::Arc, thread};
+///
+/// let r = Arc::new(());
+/// let a = SyncArena::new();
+/// thread::scope(|s| {
+///   for t in 0..8 {
+///     let (a, r) = (&a, &r);
+///     s.spawn(move || {
+///       let xs: Vec<&mut (Arc<()>, usize)> = (0..300).map(|i| a.alloc((r.clone(), t * 1000 + i))).collect();
+///       assert!(xs.iter().enumerate().all(|(i, x)| x.1 == t * 1000 + i));
+///     });
+///   }
+/// });
+/// assert_eq!(a.len(), 2400);
+/// assert_eq!(Arc::strong_count(&r), 2401);
+/// drop(a);
+/// assert_eq!(Arc::strong_count(&r), 1);
+/// ```
+pub struct SyncArena<T> {
+  // points to the last chunk in `chunks`
+  cur: AtomicPtr<Chunk<T>>,
+  chunks: Mutex<Vec<NonNull<Chunk<T>>>>,
+  _marker: PhantomData<T>,
+}
+
+struct Chunk<T> {
+  data: *mut MaybeUninit<T>,
+  cap: usize,
+  // the number of claimed slots, which may exceed `cap` after failed claims
+  next: AtomicUsize,
+}
+
+unsafe impl<T: Send> Send for SyncArena<T> {}
+unsafe impl<T: Send> Sync for SyncArena<T> {}
+
+impl<T> SyncArena<T> {
+  /// Creates an empty arena, which doesn't allocate until the first allocation.
+  #[inline]
+  pub const fn new() -> Self {
+    SyncArena { cur: AtomicPtr::new(ptr::null_mut()), chunks: Mutex::new(Vec::new()), _marker: PhantomData }
+  }
+
+  /// Allocates a value in the arena, and returns a mutable reference to it.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::SyncArena;
+  ///
+  /// let a = SyncArena::new();
+  /// let x = a.alloc(1);
+  /// let y = a.alloc(2);
+  /// *x += *y;
+  /// assert_eq!((*x, *y), (3, 2));
+  /// let b = SyncArena::new();
+  /// for _ in 0..10000 { b.alloc(()); }
+  /// assert_eq!(b.len(), 10000);
+  /// ```
+  pub fn alloc(&self, t: T) -> &mut T {
+    loop {
+      let c = self.cur.load(Ordering::Acquire);
+      if !c.is_null() {
+        let c = unsafe { &*c };
+        let i = c.next.fetch_add(1, Ordering::Relaxed);
+        if i < c.cap {
+          return unsafe {
+            let p = c.data.add(i) as *mut T;
+            p.write(t);
+            &mut *p
+          };
+        }
+      }
+      self.grow(c);
+    }
+  }
+
+  /// Returns the number of objects allocated in the arena.
+  ///
+  /// Allocations by other threads may not be counted until they return.
+  pub fn len(&self) -> usize {
+    let chunks = self.chunks.lock().unwrap_or_else(|e| e.into_inner());
+    chunks.iter().map(|c| {
+      let c = unsafe { c.as_ref() };
+      c.next.load(Ordering::Relaxed).min(c.cap)
+    }).sum()
+  }
+
+  /// Returns `true` if no object has been allocated in the arena.
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+  /// Allocates a new current chunk, unless another thread has replaced `full` already.
+  #[cold]
+  fn grow(&self, full: *mut Chunk<T>) {
+    let mut chunks = self.chunks.lock().unwrap_or_else(|e| e.into_inner());
+    if self.cur.load(Ordering::Acquire) != full { return; }
+    let cap = match chunks.last() {
+      _ if mem::size_of::<T>() == 0 => usize::MAX,
+      Some(c) => (unsafe { c.as_ref() }.cap * 2).min(HUGE_PAGE / mem::size_of::<T>()),
+      None => PAGE / mem::size_of::<T>(),
+    }.max(1);
+    let data = Box::into_raw(Box::<[T]>::new_uninit_slice(cap)) as *mut MaybeUninit<T>;
+    let chunk = Box::into_raw(Box::new(Chunk { data, cap, next: AtomicUsize::new(0) }));
+    self.cur.store(chunk, Ordering::Release);
+    chunks.push(unsafe { NonNull::new_unchecked(chunk) });
+  }
+}
+
+impl<T> Default for SyncArena<T> {
+  /// Equivalent to calling `SyncArena::<T>::new()`.
+  fn default() -> Self { Self::new() }
+}
+
+unsafe impl<#[may_dangle] T> Drop for SyncArena<T> {
+  fn drop(&mut self) {
+    let chunks = self.chunks.get_mut().unwrap_or_else(|e| e.into_inner());
+    for &c in chunks.iter() {
+      unsafe {
+        let mut c = Box::from_raw(c.as_ptr());
+        let len = (*c.next.get_mut()).min(c.cap);
+        ptr::drop_in_place(ptr::slice_from_raw_parts_mut(c.data as *mut T, len));
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(c.data, c.cap)));
+      }
+    }
+  }
+}
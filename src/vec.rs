@@ -0,0 +1,203 @@
+use core::{alloc::Layout, cell::UnsafeCell, fmt, mem::{self, ManuallyDrop}, ops::{Deref, DerefMut}, ptr::{self, NonNull}, slice};
+use crate::{AllocError, Bump, bump::Inner};
+
+/// A growable vector, whose buffer is allocated in a `Bump`.
+///
+/// Pushing elements doesn't touch the global allocator. When the buffer is full, it grows in place if it is the most
+/// recent allocation in the arena, and otherwise the elements are moved to a new buffer twice as large. The old buffer
+/// is never reused, and is reported by `Bump::wasted_bytes`.
+///
+/// If the vector is dropped, its elements are dropped, but its buffer stays in the arena. `into_slice` freezes it
+/// into a slice that lives as long as the arena instead.
+///
+/// ## Example
+///
+/// ```
+/// use arena::{Bump, ArenaVec};
+///
+/// let b = Bump::new();
+/// let mut v = ArenaVec::new_in(&b);
+/// for i in 0..100 { v.push(i); }
+/// v.extend(100..200);
+/// assert_eq!((v.len(), v[150]), (200, 150));
+/// v[0] = -1;
+/// let s = v.into_slice();
+/// assert_eq!(&s[..3], &[-1, 1, 2]);
+/// ```
+pub struct ArenaVec<'a, T> {
+  bump: &'a UnsafeCell<Inner>,
+  ptr: NonNull<T>,
+  len: usize,
+  cap: usize,
+}
+
+impl<'a, T> ArenaVec<'a, T> {
+  /// Creates an empty vector in `bump`, which doesn't allocate until the first element is pushed.
+  #[inline]
+  pub fn new_in(bump: &'a Bump) -> Self {
+    let cap = if mem::size_of::<T>() == 0 { usize::MAX } else { 0 };
+    ArenaVec { bump: &bump.0, ptr: NonNull::dangling(), len: 0, cap }
+  }
+
+  /// Creates an empty vector in `bump`, with room for `n` elements.
+  #[inline]
+  pub fn with_capacity_in(n: usize, bump: &'a Bump) -> Self {
+    let mut v = Self::new_in(bump);
+    v.reserve(n);
+    v
+  }
+
+  /// Returns the number of elements.
+  #[inline]
+  pub fn len(&self) -> usize { self.len }
+
+  /// Returns `true` if the vector has no elements.
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.len == 0 }
+
+  /// Returns the number of elements the vector can hold without growing.
+  #[inline]
+  pub fn capacity(&self) -> usize { self.cap }
+
+  /// Appends an element to the back of the vector.
+  #[inline]
+  pub fn push(&mut self, t: T) {
+    if self.len == self.cap { self.reserve(1); }
+    unsafe { self.ptr.as_ptr().add(self.len).write(t); }
+    self.len += 1;
+  }
+
+  /// Removes the last element and returns it, or `None` if the vector is empty.
+  #[inline]
+  pub fn pop(&mut self) -> Option<T> {
+    if self.len == 0 { return None; }
+    self.len -= 1;
+    unsafe { Some(self.ptr.as_ptr().add(self.len).read()) }
+  }
+
+  /// Reserves capacity for at least `additional` more elements.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::{Bump, ArenaVec};
+  ///
+  /// let b = Bump::new();
+  /// let mut v = ArenaVec::new_in(&b);
+  /// v.push(0u64);
+  /// v.reserve(100);
+  /// assert!(v.capacity() >= 101);
+  /// // the buffer is the most recent allocation, so it grew in place
+  /// assert_eq!(b.wasted_bytes(), 0);
+  /// b.alloc(0u64);
+  /// v.extend(1..1000);
+  /// assert!(b.wasted_bytes() >= 101 * 8);
+  /// assert!(v.iter().copied().eq(0..1000));
+  /// ```
+  pub fn reserve(&mut self, additional: usize) {
+    if self.cap - self.len >= additional { return; }
+    let cap = match self.len.checked_add(additional) {
+      Some(n) => n.max(self.cap * 2).max(4),
+      None => AllocError::capacity_overflow(()).raise(),
+    };
+    let (old, new) = match (Layout::array::<T>(self.cap), Layout::array::<T>(cap)) {
+      (Ok(old), Ok(new)) => (old, new),
+      _ => AllocError::capacity_overflow(()).raise(),
+    };
+    unsafe {
+      let inner = &mut *self.bump.get();
+      if !inner.resize_last(self.ptr.as_ptr() as *mut u8, old.size(), new.size()) {
+        let p = inner.alloc_layout(new) as *mut T;
+        p.copy_from_nonoverlapping(self.ptr.as_ptr(), self.len);
+        inner.waste(old.size());
+        self.ptr = NonNull::new_unchecked(p);
+      }
+    }
+    self.cap = cap;
+  }
+
+  /// Consumes the vector, and returns its elements as a slice that lives as long as the arena.
+  ///
+  /// The elements are never dropped afterwards, like those allocated by `Bump::alloc_slice`. The spare capacity is
+  /// given back to the arena if the buffer is the most recent allocation, otherwise it is wasted.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::{Bump, ArenaVec};
+  ///
+  /// let b = Bump::new();
+  /// let mut v = ArenaVec::with_capacity_in(10, &b);
+  /// v.extend([1u8, 2, 3].iter().copied());
+  /// let s = v.into_slice();
+  /// assert_eq!(b.alloc(4u8) as *mut u8, s.as_mut_ptr().wrapping_add(3));
+  /// assert_eq!(b.wasted_bytes(), 0);
+  /// ```
+  pub fn into_slice(self) -> &'a mut [T] {
+    let v = ManuallyDrop::new(self);
+    unsafe {
+      if mem::size_of::<T>() != 0 {
+        let (cap, len) = (v.cap * mem::size_of::<T>(), v.len * mem::size_of::<T>());
+        let inner = &mut *v.bump.get();
+        if !inner.resize_last(v.ptr.as_ptr() as *mut u8, cap, len) { inner.waste(cap - len); }
+      }
+      slice::from_raw_parts_mut(v.ptr.as_ptr(), v.len)
+    }
+  }
+}
+
+impl<T: Copy> ArenaVec<'_, T> {
+  /// Appends all elements of `s` to the back of the vector.
+  #[inline]
+  pub fn extend_from_slice(&mut self, s: &[T]) {
+    self.reserve(s.len());
+    unsafe { self.ptr.as_ptr().add(self.len).copy_from_nonoverlapping(s.as_ptr(), s.len()); }
+    self.len += s.len();
+  }
+}
+
+impl<T> Deref for ArenaVec<'_, T> {
+  type Target = [T];
+  #[inline]
+  fn deref(&self) -> &[T] { unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) } }
+}
+
+impl<T> DerefMut for ArenaVec<'_, T> {
+  #[inline]
+  fn deref_mut(&mut self) -> &mut [T] { unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) } }
+}
+
+impl<T> Extend<T> for ArenaVec<'_, T> {
+  fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+    let iter = iter.into_iter();
+    self.reserve(iter.size_hint().0);
+    for t in iter { self.push(t); }
+  }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArenaVec<'_, T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { fmt::Debug::fmt(&**self, f) }
+}
+
+impl<T> Drop for ArenaVec<'_, T> {
+  /// Drops all elements, and leaves the buffer in the arena.
+  ///
+  /// ## Example
+  ///
+  /// ```
+  /// use arena::{Bump, ArenaVec};
+  /// use std::rc::Rc;
+  ///
+  /// let x = Rc::new(());
+  /// let b = Bump::new();
+  /// let mut v = ArenaVec::new_in(&b);
+  /// for _ in 0..10 { v.push(x.clone()); }
+  /// v.pop();
+  /// assert_eq!(Rc::strong_count(&x), 10);
+  /// drop(v);
+  /// assert_eq!(Rc::strong_count(&x), 1);
+  /// ```
+  fn drop(&mut self) {
+    unsafe { ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.ptr.as_ptr(), self.len)); }
+  }
+}